@@ -0,0 +1,54 @@
+use std::process::Command;
+
+// MI: embed build and dependency versions for `ore version`
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ORE_CLI_GIT_COMMIT={}", git_commit);
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ORE_CLI_RUSTC_VERSION={}", rustc_version);
+
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=ORE_CLI_TARGET={}", target);
+
+    let lock = std::fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (name, key) in [
+        ("drillx", "ORE_CLI_DRILLX_VERSION"),
+        ("ore-api", "ORE_CLI_ORE_API_VERSION"),
+        ("solana-sdk", "ORE_CLI_SOLANA_SDK_VERSION"),
+    ] {
+        println!("cargo:rustc-env={}={}", key, locked_version(&lock, name));
+    }
+}
+
+// Version of a package in Cargo.lock, the first match if several are locked
+fn locked_version(lock: &str, name: &str) -> String {
+    let needle = format!("name = \"{}\"", name);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == needle {
+            if let Some(version) = lines
+                .next()
+                .and_then(|line| line.trim().strip_prefix("version = "))
+            {
+                return version.trim_matches('"').to_string();
+            }
+        }
+    }
+    "unknown".to_string()
+}
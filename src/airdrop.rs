@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use colored::*;
+use solana_program::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_rpc_client::spinner;
+use solana_sdk::signature::Signer;
+
+use crate::{args::AirdropArgs, Error, Miner};
+
+const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+const AIRDROP_CONFIRM_RETRIES: usize = 30;
+const AIRDROP_CONFIRM_DELAY: u64 = 1_000;
+
+impl Miner {
+    pub async fn airdrop(&self, args: AirdropArgs) -> Result<(), Error> {
+        // Refuse to airdrop on mainnet
+        let genesis_hash = self
+            .rpc_client
+            .get_genesis_hash()
+            .await
+            .map_err(|err| Error::Failed(format!("Failed to fetch genesis hash: {}", err)))?;
+        if genesis_hash.to_string().eq(MAINNET_GENESIS_HASH) {
+            return Err(Error::Failed(
+                "Airdrops are not available on mainnet".to_string(),
+            ));
+        }
+
+        // Request airdrop to the fee payer
+        let fee_payer = self.fee_payer()?.pubkey();
+        let lamports = sol_to_lamports(args.amount);
+        let progress_bar = spinner::new_progress_bar();
+        progress_bar.set_message(format!(
+            "Requesting {} SOL to {}...",
+            lamports_to_sol(lamports),
+            fee_payer
+        ));
+        let sig = match self.rpc_client.request_airdrop(&fee_payer, lamports).await {
+            Ok(sig) => sig,
+            Err(err) => {
+                progress_bar.finish_and_clear();
+                return Err(err.into());
+            }
+        };
+
+        // Confirm airdrop
+        for _ in 0..AIRDROP_CONFIRM_RETRIES {
+            if let Ok(true) = self.rpc_client.confirm_transaction(&sig).await {
+                progress_bar.finish_with_message(format!("{} {}", "OK".bold().green(), sig));
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(AIRDROP_CONFIRM_DELAY)).await;
+        }
+        progress_bar.finish_and_clear();
+        Err(Error::Failed(format!("Airdrop not confirmed: {}", sig)))
+    }
+}
@@ -1,4 +1,18 @@
-use clap::{arg, Parser};
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+
+use crate::utils::{parse_duration, parse_multiplier};
+
+#[derive(Parser, Debug)]
+pub struct AirdropArgs {
+    #[arg(
+        value_name = "AMOUNT",
+        help = "The amount of SOL to request for the fee payer.",
+        default_value = "1"
+    )]
+    pub amount: f64,
+}
 
 #[derive(Parser, Debug)]
 pub struct BalanceArgs {
@@ -7,6 +21,9 @@ pub struct BalanceArgs {
         help = "The account address to fetch the balance of."
     )]
     pub address: Option<String>,
+
+    #[arg(long, help = "List every SPL token the account holds, not just ORE.")]
+    pub all_tokens: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -19,6 +36,35 @@ pub struct BenchmarkArgs {
         default_value = "1"
     )]
     pub cores: u64,
+
+    #[arg(
+        long,
+        value_name = "THREADS_PER_CORE",
+        help = "The number of hashing threads to run on each core during the benchmark, one per SMT sibling. Linux only.",
+        default_value = "1"
+    )]
+    pub threads_per_core: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "The number of seconds to hash before the measured window starts",
+        default_value = "2"
+    )]
+    pub warmup: u64,
+
+    #[arg(
+        long,
+        help = "Estimate the difficulty distribution and ORE per hour at the measured hashpower, from the current on-chain config"
+    )]
+    pub estimate_rewards: bool,
+
+    #[arg(
+        long,
+        value_name = "CHALLENGE",
+        help = "The 32 byte challenge to hash against, as 64 hex characters or base58. Defaults to all zeros."
+    )]
+    pub challenge: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -32,24 +78,104 @@ pub struct ClaimArgs {
     )]
     pub amount: Option<f64>,
 
+    #[arg(
+        long,
+        value_name = "GRAINS",
+        help = "The exact amount of rewards to claim in token base units. Takes precedence over AMOUNT."
+    )]
+    pub amount_raw: Option<u64>,
+
     #[arg(
         long,
         value_name = "WALLET_ADDRESS",
         help = "Wallet address to receive claimed tokens."
     )]
     pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Keep running and claim the full balance every interval once it exceeds --threshold, without asking for confirmation.",
+        conflicts_with_all = ["amount", "amount_raw"]
+    )]
+    pub schedule: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "The minimum claimable balance for a scheduled claim.",
+        default_value = "0",
+        requires = "schedule"
+    )]
+    pub threshold: f64,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Randomize each scheduled interval by up to this percentage.",
+        default_value = "10",
+        requires = "schedule",
+        value_parser = clap::value_parser!(u64).range(0..=100)
+    )]
+    pub jitter: u64,
+
+    #[arg(
+        long,
+        short,
+        help = "Skip typing the end of the --to address to confirm it."
+    )]
+    pub yes: bool,
 }
 
 #[derive(Parser, Debug)]
 pub struct CloseArgs {}
 
 #[derive(Parser, Debug)]
-pub struct ConfigArgs {}
+pub struct ConfigArgs {
+    #[arg(
+        long,
+        help = "Keep polling the config and print a line whenever the last reset, min difficulty or base reward rate changes."
+    )]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How often to poll the config with --watch.",
+        default_value = "5"
+    )]
+    pub watch_interval: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct DoctorArgs {
+    #[arg(
+        long,
+        short,
+        value_name = "CORES_COUNT",
+        help = "The number of CPU cores you intend to allocate to mining."
+    )]
+    pub cores: Option<u64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct FeeArgs {
+    #[arg(
+        long,
+        value_name = "UNITS",
+        help = "The compute unit limit to price, as used by mine transactions.",
+        default_value = "500000"
+    )]
+    pub compute_budget: u32,
+}
 
 #[cfg(feature = "admin")]
 #[derive(Parser, Debug)]
 pub struct InitializeArgs {}
 
+#[derive(Parser, Debug)]
+pub struct LookupTableArgs {}
+
 #[derive(Parser, Debug)]
 pub struct MineArgs {
     #[arg(
@@ -70,6 +196,31 @@ pub struct MineArgs {
     )]
     pub threads: Option<u64>,
 
+    #[arg(
+        long,
+        value_name = "CORE_IDS",
+        help = "Comma separated ids of the cores to pin hashing threads to, e.g. 2,4,6,8. Takes precedence over --cores.",
+        value_delimiter = ',',
+        conflicts_with = "threads"
+    )]
+    pub core_mask: Vec<usize>,
+
+    #[arg(
+        long,
+        help = "Skip efficiency cores on hybrid CPUs, where they can be detected (Linux on Intel 12th gen and later). Falls back to all selected cores elsewhere.",
+        conflicts_with = "threads"
+    )]
+    pub performance_cores_only: bool,
+
+    #[arg(
+        long,
+        value_name = "THREADS_PER_CORE",
+        help = "The number of hashing threads to run on each core when mining with --cores, each pinned to its own SMT (hyperthreading) sibling of the core. Linux only; run the benchmark with the same value to compare.",
+        default_value = "1",
+        conflicts_with = "threads"
+    )]
+    pub threads_per_core: u64,
+
     #[arg(
         long,
         short,
@@ -119,8 +270,9 @@ pub struct MineArgs {
         long,
         short,
         value_name = "EXTRA_FEE_PERCENT",
-        help = "The extra percentage that the miner feels deserves to pay more of the priority fee. A positive integer in the range 0..100 [inclusive] is preferred (although integer > 100 is possible, but not recommended), and the final priority fee cannot exceed the priority fee cap.",
-        default_value = "0"
+        help = "The extra percentage that the miner feels deserves to pay more of the priority fee, in the range 0..100 [inclusive]. The final priority fee cannot exceed the priority fee cap.",
+        default_value = "0",
+        value_parser = clap::value_parser!(u64).range(0..=100)
     )]
     pub extra_fee_percent: u64,
 
@@ -140,17 +292,385 @@ pub struct MineArgs {
         default_value = "25"
     )]
     pub messaging_diff: u32,
+
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        help = "Send a summary of rounds mined, rewards, balance and hashpower to the messaging channels (if configured) at this interval."
+    )]
+    pub heartbeat: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SLOT_TIMING",
+        help = "Estimate the cluster time from slot height and use the more conservative of it and the clock sysvar when calculating the cutoff.",
+        default_value = "false"
+    )]
+    pub slot_timing: bool,
+
+    #[arg(
+        long,
+        value_name = "UNITS",
+        help = "The compute unit limit for mine transactions. The auth and mine instructions consume roughly 3,200 CUs on-chain.",
+        default_value = "500000"
+    )]
+    pub compute_budget: u32,
+
+    #[arg(
+        long,
+        value_name = "UNITS",
+        help = "The extra compute units added when a reset instruction is bundled. The reset instruction consumes roughly 12,200 CUs on-chain.",
+        default_value = "100000"
+    )]
+    pub reset_extra_cu: u32,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "The chance, in percent, of bundling a reset instruction when the epoch needs one. Higher values reset sooner but more miners pay for a reset that only one of them lands; use 100 on low-population clusters such as devnet.",
+        default_value = "1",
+        value_parser = clap::value_parser!(u64).range(0..=100)
+    )]
+    pub reset_probability: u64,
+
+    #[arg(
+        long,
+        help = "Wait for the next epoch reset instead of submitting when the top bus holds less than --min-bus-rewards, so no fee is spent on a bus that cannot pay out."
+    )]
+    pub ignore_empty_buses: bool,
+
+    #[arg(
+        long,
+        value_name = "ORE",
+        help = "Bus rewards below which --ignore-empty-buses waits for the reset. Defaults to the estimated reward for the solution's difficulty.",
+        requires = "ignore_empty_buses"
+    )]
+    pub min_bus_rewards: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "QUIET",
+        help = "Suppress the mining progress spinner, e.g. when logging to a file.",
+        default_value = "false"
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "The minimum time between mining progress updates.",
+        default_value = "100"
+    )]
+    pub progress_interval: u64,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        help = "Halt mining when the SOL spent on fees outpaces the value of ORE earned over this many consecutive rounds.",
+        requires = "ore_price"
+    )]
+    pub profit_guard: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "SOL_PER_ORE",
+        help = "The price of 1 ORE in SOL, used by the profit guard and fee fraction cap to value rewards."
+    )]
+    pub ore_price: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Pause for this many seconds instead of exiting when the profit guard trips."
+    )]
+    pub profit_guard_pause: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "ORE_THRESHOLD",
+        help = "Stake the ORE that reaches your wallet while mining, e.g. from claims, back into the proof whenever it adds up to this amount. ORE held before mining started is not staked. Staking runs while the next round hashes."
+    )]
+    pub compound: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "When compounding, stake only enough ORE to reach this multiplier (1.0 to 2.0).",
+        value_parser = parse_multiplier,
+        requires = "compound"
+    )]
+    pub target_multiplier: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "Cap the priority fee of each round at this fraction (0..1) of the estimated reward, valued with --ore-price.",
+        requires = "ore_price"
+    )]
+    pub max_fee_fraction: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Add a tip transfer of this many lamports to each mine transaction, for Jito-enabled RPCs. Zero disables the tip.",
+        default_value = "0"
+    )]
+    pub jito_tip: u64,
+
+    #[arg(
+        long,
+        value_name = "PUBKEY",
+        help = "Jito tip account to send the tip to. Defaults to a random known tip account each round.",
+        requires = "jito_tip"
+    )]
+    pub jito_tip_account: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SOL",
+        help = "Warn, and alert the configured webhooks, when the fee payer balance drops below this amount of SOL."
+    )]
+    pub fee_payer_min_balance: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Pause this many seconds each round while the fee payer balance is below --fee-payer-min-balance.",
+        requires = "fee_payer_min_balance"
+    )]
+    pub fee_payer_low_pause: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Show a desktop notification for each landed solution at or above the messaging difficulty."
+    )]
+    pub desktop_notify: bool,
+
+    #[arg(
+        long,
+        value_name = "MILLISECONDS",
+        help = "How often to poll for the next challenge after a submission. Lower values start rounds sooner at the cost of more RPC requests. Backs off automatically when the RPC rate limits.",
+        default_value = "1000"
+    )]
+    pub challenge_refresh_interval: u64,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        help = "Back off after this many consecutive submitted rounds earn no rewards, e.g. when all buses are empty.",
+        requires = "idle_backoff"
+    )]
+    pub idle_rounds: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to sleep between bus checks while backing off.",
+        requires = "idle_rounds"
+    )]
+    pub idle_backoff: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Sleep before the next attempt once --cool-down-after rounds in a row did not land, to avoid spending fees into a congested network. The sleep doubles with each further failed round, up to --cool-down-max."
+    )]
+    pub cool_down: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "ROUNDS",
+        help = "Consecutive rounds that did not land before the cool-down starts.",
+        default_value = "2"
+    )]
+    pub cool_down_after: u64,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Longest cool-down sleep between failed rounds.",
+        default_value = "300"
+    )]
+    pub cool_down_max: u64,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stack size of each hashing thread. Defaults to the platform default."
+    )]
+    pub thread_stack_size: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Run the miner at this nice level (-20..19) so it yields to other work. Unix only; raising priority (negative values) usually requires root.",
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    pub nice: Option<i32>,
+
+    #[arg(
+        long,
+        value_name = "PATH_OR_ADDR",
+        help = "Stream round events as newline delimited JSON to clients connecting to this host:port address, e.g. localhost:9000, or this unix socket path. An existing file at the path is only replaced if it is a socket."
+    )]
+    pub event_socket: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Warn when local time and the cluster clock drift apart by more than this many seconds. The cutoff is calculated from the cluster clock.",
+        default_value = "5"
+    )]
+    pub max_clock_skew: u64,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Stop mining after this much wall-clock time, e.g. 8h, 45m or 1h30m. The round in progress is finished first.",
+        value_parser = parse_duration
+    )]
+    pub max_duration: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "When --expected-min-difficulty is not met by the cutoff, submit the best hash right away and keep hashing through the risk time. The pending transaction is replaced only if a hash at least this many difficulty levels higher is found before it confirms. A replaced transaction may still land, and the replacement then fails but still pays its fee."
+    )]
+    pub submit_only_if_improves: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Keep hashing the current challenge while the submission confirms. If it does not land, the best hash found meanwhile is resubmitted without mining the challenge again. Uses extra CPU during the confirmation wait."
+    )]
+    pub pipeline: bool,
+
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "max-difficulty hashes until the cutoff and submits the best hash. earliest-min stops hashing at the first hash that reaches --expected-min-difficulty and submits it as soon as the program accepts a new hash, trading reward for a solution that is ready before the deadline.",
+        default_value = "max-difficulty",
+        conflicts_with_all = ["submit_only_if_improves", "pipeline"]
+    )]
+    pub submit_strategy: SubmitStrategy,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "POST a JSON summary of each landed round (wallet, difficulty, reward, fee, signature, hashrate, timestamp) to this URL. Sent in the background, the oldest reports are dropped if the server falls behind."
+    )]
+    pub report_url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SECRET",
+        help = "Shared secret sent in the X-Ore-Report-Secret header of each report. Defaults to ORE_REPORT_SECRET.",
+        requires = "report_url"
+    )]
+    pub report_secret: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Save the last searched nonce of each hashing thread, and the best hash, to this file. After a restart on the same challenge with the same threads, mining resumes from it instead of searching the same nonces again."
+    )]
+    pub nonce_file: Option<String>,
+
+    #[arg(
+        long,
+        help = "Refuse to mine unless the fee payer is a different wallet than the signer."
+    )]
+    pub separate_fee_payer_required: bool,
+
+    #[arg(
+        long,
+        value_name = "DIFFICULTY",
+        help = "Print the full stake and reward lines only for rounds at or above this difficulty. Lower rounds print a one line summary. Does not affect notifications."
+    )]
+    pub log_difficulty_threshold: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Print one aggregated stats line every interval instead of the per-round output: rounds, average and best difficulty, rewards, hashpower and SOL spent."
+    )]
+    pub output_interval: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Mine and submit a single round, then exit. Exits with status 0 if the transaction landed and 1 otherwise."
+    )]
+    pub once: bool,
+
+    #[arg(
+        long,
+        help = "Skip re-fetching the proof before each submission. By default a solution is not submitted if the challenge changed while hashing, e.g. when another rig mines the same proof."
+    )]
+    pub no_challenge_check: bool,
+
+    #[arg(
+        long,
+        help = "Print a warning for each round where the priority fee cap clamped the dynamic fee estimate. Cap hits are counted in the session summary either way."
+    )]
+    pub warn_on_fee_cap_hit: bool,
 }
 
+#[derive(Parser, Debug)]
+pub struct NotifyTestArgs {}
+
 #[derive(Parser, Debug)]
 pub struct ProofArgs {
     #[arg(value_name = "ADDRESS", help = "The address of the proof to fetch.")]
     pub address: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILE_OR_LIST",
+        help = "Fetch the proofs of many authorities at once: a comma separated list of pubkeys, or a file with one per line.",
+        conflicts_with = "address"
+    )]
+    pub authorities: Option<String>,
+
+    #[arg(
+        long,
+        help = "Sort the --authorities table by balance, highest first.",
+        requires = "authorities"
+    )]
+    pub sort_by_balance: bool,
 }
 
+#[derive(Parser, Debug)]
+pub struct RecordsArgs {}
+
 #[derive(Parser, Debug)]
 pub struct RewardsArgs {}
 
+#[derive(Parser, Debug)]
+pub struct SignArgs {
+    #[arg(
+        value_name = "FILE",
+        help = "Unsigned transaction written by --offline."
+    )]
+    pub input: String,
+
+    #[arg(
+        long,
+        short,
+        value_name = "FILE",
+        help = "Where to write the signed transaction. Defaults to overwriting the input file."
+    )]
+    pub output: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SubmitArgs {
+    #[arg(
+        value_name = "FILE",
+        help = "Signed transaction written by `ore sign`."
+    )]
+    pub input: String,
+}
+
 #[derive(Parser, Debug)]
 pub struct StakeArgs {
     #[arg(
@@ -165,6 +685,29 @@ pub struct StakeArgs {
         help = "Token account to send ORE from. Defaults to the associated token account."
     )]
     pub token_account: Option<String>,
+
+    #[arg(
+        long,
+        help = "Stake from the proof's claimable rewards instead of a token account.",
+        conflicts_with_all = ["amount", "token_account"]
+    )]
+    pub from_rewards: bool,
+
+    #[arg(
+        long,
+        value_name = "MULTIPLIER",
+        help = "Stake only enough ORE to reach this multiplier (1.0 to 2.0), given the current top balance.",
+        value_parser = parse_multiplier,
+        conflicts_with_all = ["amount", "from_rewards"]
+    )]
+    pub target_multiplier: Option<f64>,
+
+    #[arg(
+        long,
+        help = "List the signer's ORE token accounts that can be passed to --token-account, then exit.",
+        conflicts_with_all = ["amount", "token_account", "from_rewards", "target_multiplier"]
+    )]
+    pub list_token_accounts: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -177,8 +720,21 @@ pub struct TransferArgs {
         help = "The account address of the receipient."
     )]
     pub to: String,
+
+    #[arg(
+        long,
+        short,
+        help = "Skip typing the end of the recipient address to confirm it."
+    )]
+    pub yes: bool,
 }
 
+#[derive(Parser, Debug)]
+pub struct VersionArgs {}
+
+#[derive(Parser, Debug)]
+pub struct WhoamiArgs {}
+
 #[derive(Parser, Debug)]
 pub struct UpgradeArgs {
     #[arg(
@@ -187,3 +743,10 @@ pub struct UpgradeArgs {
     )]
     pub amount: Option<f64>,
 }
+
+// MI: which hash of the round gets submitted
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmitStrategy {
+    MaxDifficulty,
+    EarliestMin,
+}
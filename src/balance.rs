@@ -1,28 +1,28 @@
 use std::str::FromStr;
 
+use colored::*;
+use serde_json::{json, Value};
+use solana_client::rpc_request::TokenAccountsFilter;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
 
 use crate::{
     args::BalanceArgs,
-    utils::{amount_u64_to_string, get_proof_with_authority},
-    Miner,
+    utils::{amount_u64_to_string, require_proof},
+    Error, Miner, OutputFormat,
 };
 
 impl Miner {
-    pub async fn balance(&self, args: BalanceArgs) {
-        let signer = self.signer();
-        let address = if let Some(address) = args.address {
-            if let Ok(address) = Pubkey::from_str(&address) {
-                address
-            } else {
-                println!("Invalid address: {:?}", address);
-                return;
-            }
-        } else {
-            signer.pubkey()
+    pub async fn balance(&self, args: BalanceArgs) -> Result<(), Error> {
+        let address = match args.address {
+            Some(address) => Pubkey::from_str(&address)
+                .map_err(|_| Error::Failed(format!("Invalid address: {}", address)))?,
+            None => self.query_authority()?,
         };
-        let proof = get_proof_with_authority(&self.rpc_client, address).await;
+        if args.all_tokens {
+            self.token_balances(address).await;
+            return Ok(());
+        }
+        let proof = require_proof(&self.rpc_client, address).await?;
         let token_account_address = spl_associated_token_account::get_associated_token_address(
             &address,
             &ore_api::consts::MINT_ADDRESS,
@@ -40,6 +40,70 @@ impl Miner {
             "Balance: {} ORE\nStake: {} ORE",
             token_balance,
             amount_u64_to_string(proof.balance)
-        )
+        );
+        Ok(())
+    }
+
+    // MI: every SPL token account owned by the address, with its mint and UI amount
+    async fn token_balances(&self, address: Pubkey) {
+        let accounts = match self
+            .rpc_client
+            .get_token_accounts_by_owner(&address, TokenAccountsFilter::ProgramId(spl_token::id()))
+            .await
+        {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                println!(
+                    "{} Failed to fetch token accounts: {}",
+                    "ERROR".bold().red(),
+                    err
+                );
+                return;
+            }
+        };
+
+        // The RPC returns jsonParsed token accounts
+        let tokens: Vec<Value> = accounts
+            .into_iter()
+            .filter_map(|keyed| {
+                let data = serde_json::to_value(&keyed.account.data).ok()?;
+                let info = &data["parsed"]["info"];
+                Some(json!({
+                    "token_account": keyed.pubkey,
+                    "mint": info["mint"].as_str()?,
+                    "amount": info["tokenAmount"]["uiAmountString"].as_str()?,
+                    "ore": info["mint"]
+                        .as_str()?
+                        .eq(&ore_api::consts::MINT_ADDRESS.to_string()),
+                }))
+            })
+            .collect();
+
+        match self.output {
+            OutputFormat::Json => {
+                println!(
+                    "{}",
+                    json!({
+                        "owner": address.to_string(),
+                        "tokens": tokens,
+                    })
+                );
+            }
+            OutputFormat::Text => {
+                if tokens.is_empty() {
+                    println!("No token accounts found for {}", address);
+                    return;
+                }
+                for token in tokens.iter() {
+                    let mint = token["mint"].as_str().unwrap_or_default();
+                    let amount = token["amount"].as_str().unwrap_or_default();
+                    if token["ore"].as_bool().unwrap_or(false) {
+                        println!("{} {} ({})", amount, "ORE".bold(), mint);
+                    } else {
+                        println!("{} {}", amount, mint);
+                    }
+                }
+            }
+        }
     }
 }
@@ -1,45 +1,88 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use colored::*;
 use drillx::equix;
+use ore_api::state::Config;
 use solana_rpc_client::spinner;
 
-use crate::{args::BenchmarkArgs, Miner};
+use crate::{
+    args::BenchmarkArgs,
+    cpu_topology::{sibling_cores, thread_siblings},
+    mine::estimate_reward,
+    utils::{amount_u64_to_f64, amount_u64_to_string, get_config},
+    Error, Miner,
+};
 
 const TEST_DURATION: i64 = 30;
 
+// MI: assumptions of the reward estimate
+const ESTIMATE_HASHING_SECS: f64 = 55.0;
+const ESTIMATE_ROUND_SECS: f64 = 60.0;
+const ESTIMATE_MIN_CHANCE: f64 = 0.001;
+
 impl Miner {
-    pub async fn benchmark(&self, args: BenchmarkArgs) {
+    pub async fn benchmark(&self, args: BenchmarkArgs) -> Result<(), Error> {
         // Check num cores
         self.check_num_cores(args.cores);
+        let threads_per_core = args.threads_per_core.max(1);
+        let warmup = args.warmup;
 
         // Dispatch job to each thread
-        let challenge = [0; 32];
+        let challenge = match args.challenge.as_deref().map(parse_challenge) {
+            None => [0; 32],
+            Some(Ok(challenge)) => challenge,
+            Some(Err(err)) => {
+                println!("{} Invalid challenge: {}", "ERROR".bold().red(), err);
+                return Ok(());
+            }
+        };
+        println!("Challenge: {}", bs58::encode(challenge).into_string());
         let progress_bar = Arc::new(spinner::new_progress_bar());
         progress_bar.set_message(format!(
             "Benchmarking. This will take {} sec...",
-            TEST_DURATION
+            (warmup as i64).saturating_add(TEST_DURATION)
         ));
-        let core_ids = core_affinity::get_core_ids().unwrap();
+        let core_ids: Vec<_> = core_affinity::get_core_ids()
+            .unwrap()
+            .into_iter()
+            .filter(|i| (i.id as u64).lt(&args.cores))
+            .collect();
+        // MI: the threads of a core run on its SMT siblings, one each, as they do when mining
+        let core_ids = sibling_cores(&core_ids, threads_per_core as usize, thread_siblings)
+            .map_err(Error::Failed)?;
+        let threads = core_ids.len() as u64;
         let handles: Vec<_> = core_ids
             .into_iter()
-            .map(|i| {
+            .enumerate()
+            .map(|(t, i)| {
                 std::thread::spawn({
                     move || {
-                        let timer = Instant::now();
-                        let first_nonce = u64::MAX
-                            .saturating_div(args.cores)
-                            .saturating_mul(i.id as u64);
+                        // Pin to core
+                        let _ = core_affinity::set_for_current(i);
+
+                        let first_nonce = u64::MAX.saturating_div(threads).saturating_mul(t as u64);
                         let mut nonce = first_nonce;
                         let mut memory = equix::SolverMemory::new();
-                        loop {
-                            // Return if core should not be used
-                            if (i.id as u64).ge(&args.cores) {
-                                return 0;
-                            }
 
-                            // Pin to core
-                            let _ = core_affinity::set_for_current(i);
+                        // Warm up caches and cpu frequency without counting hashes
+                        let timer = Instant::now();
+                        while timer.elapsed().as_secs().lt(&warmup) {
+                            let _hx = drillx::hash_with_memory(
+                                &mut memory,
+                                &challenge,
+                                &nonce.to_le_bytes(),
+                            );
+                            nonce += 1;
+                        }
+                        let warmup_count = nonce - first_nonce;
 
+                        // Start measured window
+                        let timer = Instant::now();
+                        let measured_nonce = nonce;
+                        loop {
                             // Create hash
                             let _hx = drillx::hash_with_memory(
                                 &mut memory,
@@ -56,25 +99,130 @@ impl Miner {
                             }
                         }
 
-                        // Return hash count
-                        nonce - first_nonce
+                        // Return hash counts
+                        (warmup_count, nonce - measured_nonce)
                     }
                 })
             })
             .collect();
 
-        // Join handles and return best nonce
+        // Join handles and sum hash counts
+        let mut total_warmup_nonces = 0;
         let mut total_nonces = 0;
         for h in handles {
-            if let Ok(count) = h.join() {
+            if let Ok((warmup_count, count)) = h.join() {
+                total_warmup_nonces += warmup_count;
                 total_nonces += count;
             }
         }
 
         // Update log
-        progress_bar.finish_with_message(format!(
-            "Hashpower: {} H/sec",
-            total_nonces.saturating_div(TEST_DURATION as u64),
+        if warmup.gt(&0) {
+            progress_bar.println(format!(
+                "Warmup hashpower: {} H/sec ({} sec)",
+                total_warmup_nonces.saturating_div(warmup),
+                warmup
+            ));
+        }
+        let (fresh, reused) = round_startup_latency(&challenge);
+        progress_bar.println(format!(
+            "Round startup: {:.2} ms with fresh solver memory, {:.2} ms reused",
+            fresh.as_secs_f64() * 1_000.0,
+            reused.as_secs_f64() * 1_000.0
         ));
+        let hashpower = total_nonces.saturating_div(TEST_DURATION as u64);
+        progress_bar.finish_with_message(format!("Hashpower: {} H/sec", hashpower));
+
+        if args.estimate_rewards {
+            let config = get_config(&self.rpc_client).await;
+            print_reward_estimate(&config, hashpower);
+        }
+        Ok(())
     }
 }
+
+// MI: each hash reaches difficulty d with chance 2^-d, so the best of n hashes in a round
+// reaches d with chance 1 - (1 - 2^-d)^n. Rewards double with every level above the min.
+fn print_reward_estimate(config: &Config, hashpower: u64) {
+    let hashes = hashpower as f64 * ESTIMATE_HASHING_SECS;
+    let reaches = |difficulty: u32| -(-hashes * 0.5f64.powi(difficulty as i32)).exp_m1();
+    let min_difficulty = config.min_difficulty as u32;
+
+    println!("\nReward estimate (an estimate, not a guarantee)");
+    println!(
+        "  Assumes {} sec of hashing per {} sec round, every round lands, and a 1x stake multiplier.",
+        ESTIMATE_HASHING_SECS, ESTIMATE_ROUND_SECS
+    );
+    println!("  Network conditions, bus limits, transaction fees and failed submissions are not included.");
+    println!(
+        "  Min difficulty: {}, base reward: {} ORE",
+        min_difficulty,
+        amount_u64_to_string(config.base_reward_rate)
+    );
+    println!(
+        "  {:<12}{:>10}{:>16}",
+        "Difficulty", "Chance", "Reward (ORE)"
+    );
+    let mut expected = 0.0;
+    for difficulty in min_difficulty..64 {
+        let chance = reaches(difficulty) - reaches(difficulty + 1);
+        let reward = estimate_reward(config, 1.0, difficulty);
+        expected += chance * amount_u64_to_f64(reward);
+        if chance.ge(&ESTIMATE_MIN_CHANCE) {
+            println!(
+                "  {:<12}{:>9.1}%{:>16}",
+                difficulty,
+                chance * 100.0,
+                amount_u64_to_string(reward)
+            );
+        }
+    }
+    println!(
+        "  Below min difficulty: {:.1}% of rounds earn nothing",
+        (1.0 - reaches(min_difficulty)) * 100.0
+    );
+    println!(
+        "  Expected: {:.6} ORE/round, {:.6} ORE/hour",
+        expected,
+        expected * 3_600.0 / ESTIMATE_ROUND_SECS
+    );
+}
+
+// MI: a fixed challenge makes results comparable across machines and runs
+fn parse_challenge(input: &str) -> Result<[u8; 32], String> {
+    let input = input.trim();
+    let bytes = if input.len().eq(&64) && input.chars().all(|c| c.is_ascii_hexdigit()) {
+        (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&input[i..i + 2], 16).map_err(|err| err.to_string()))
+            .collect::<Result<Vec<u8>, String>>()?
+    } else {
+        bs58::decode(input)
+            .into_vec()
+            .map_err(|_| "expected 64 hex characters or base58".to_string())?
+    };
+    <[u8; 32]>::try_from(bytes.as_slice())
+        .map_err(|_| format!("expected 32 bytes, got {}", bytes.len()))
+}
+
+// MI: time from spawning a hashing thread to its first hash, with and without reusing memory
+fn round_startup_latency(challenge: &[u8; 32]) -> (Duration, Duration) {
+    let challenge = *challenge;
+    let first_hash = move |memory: Option<equix::SolverMemory>| {
+        let timer = Instant::now();
+        std::thread::spawn(move || {
+            let mut memory = memory.unwrap_or_else(equix::SolverMemory::new);
+            let _hx = drillx::hash_with_memory(&mut memory, &challenge, &0u64.to_le_bytes());
+            memory
+        })
+        .join()
+        .map(|memory| (timer.elapsed(), memory))
+    };
+    let Ok((fresh, memory)) = first_hash(None) else {
+        return (Duration::ZERO, Duration::ZERO);
+    };
+    let reused = first_hash(Some(memory))
+        .map(|(reused, _)| reused)
+        .unwrap_or_default();
+    (fresh, reused)
+}
@@ -1,8 +1,5 @@
-use crate::Miner;
-use ore_api::{
-    consts::{BUS_ADDRESSES, TOKEN_DECIMALS},
-    state::Bus,
-};
+use crate::{utils::amount_u64_to_string, Error, Miner};
+use ore_api::{consts::BUS_ADDRESSES, state::Bus};
 use ore_utils::AccountDeserialize;
 // use solana_program::pubkey::Pubkey;
 
@@ -23,7 +20,7 @@ impl Miner {
     // }
 
     // by DanielChrobak
-    pub async fn busses(&self) {
+    pub async fn busses(&self) -> Result<(), Error> {
         let client = self.rpc_client.clone();
         let data = client.get_multiple_accounts(&BUS_ADDRESSES).await.unwrap();
 
@@ -31,11 +28,11 @@ impl Miner {
             if let Some(account) = account {
                 let data_bytes = &account.data[..]; // Extract data bytes
                 if let Ok(bus) = Bus::try_from_bytes(data_bytes) {
-                    let rewards = (bus.rewards as f64) / 10f64.powf(TOKEN_DECIMALS as f64);
-                    println!("Bus {}: {} ORE", bus.id, rewards);
+                    println!("Bus {}: {} ORE", bus.id, amount_u64_to_string(bus.rewards));
                 }
             }
         }
+        Ok(())
     }
 
     // // MI: inspired by DanielChrobak
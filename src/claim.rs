@@ -1,30 +1,112 @@
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 
 use colored::*;
 use ore_api::consts::MINT_ADDRESS;
+use rand::Rng;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
-use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::ClaimArgs,
     cu_limits::CU_LIMIT_CLAIM,
+    hints,
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm, get_proof_with_authority},
-    Miner,
+    utils::{
+        amount_f64_to_u64, amount_u64_to_string, ask_confirm, confirm_recipient, memo_line,
+        require_proof,
+    },
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn claim(&self, args: ClaimArgs) {
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let proof = get_proof_with_authority(&self.rpc_client, pubkey).await;
+    pub async fn claim(&self, args: ClaimArgs) -> Result<(), Error> {
+        // MI: confirm the destination once, scheduled claims run unattended afterwards
+        if let Some(to) = args.to.as_ref() {
+            let Ok(to) = Pubkey::from_str(to) else {
+                return Err(Error::Failed(format!("Invalid --to address: {}", to)));
+            };
+            let sender = if self.offline.is_some() {
+                self.authority()?
+            } else {
+                self.signer()?.pubkey()
+            };
+            if !confirm_recipient(&to, &sender, args.yes) {
+                return Ok(());
+            }
+        }
+        if let Some(interval) = args.schedule {
+            return self.claim_schedule(&args, interval).await;
+        }
+        self.claim_once(&args, true).await
+    }
+
+    // MI: claim loop meant to run alongside a separate mining process
+    async fn claim_schedule(&self, args: &ClaimArgs, interval: u64) -> Result<(), Error> {
+        if self.offline.is_some() {
+            return Err(Error::Failed(
+                "--schedule cannot be combined with --offline".to_string(),
+            ));
+        }
+        self.check_signer_is_authority()?;
+        let threshold = amount_f64_to_u64(args.threshold);
+        println!(
+            "Claiming every ~{} sec once the balance exceeds {} ORE",
+            interval,
+            amount_u64_to_string(threshold)
+        );
+        loop {
+            // MI: a failed check or claim is retried on the next interval
+            match require_proof(&self.rpc_client, self.authority()?).await {
+                Ok(proof) if proof.balance.gt(&0) && proof.balance.ge(&threshold) => {
+                    println!(
+                        "Claimable balance {} ORE reached the threshold",
+                        amount_u64_to_string(proof.balance)
+                    );
+                    if let Err(err) = self.claim_once(args, false).await {
+                        println!("{} {}", "ERROR".bold().red(), err);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => println!("{}", hints::with_hint(&err.to_string(), false)),
+            }
+
+            // Jitter the interval so claims don't land at predictable times
+            let jitter = interval.saturating_mul(args.jitter) as f64 / 100.0;
+            let wait = interval as f64 + rand::thread_rng().gen_range(-jitter..=jitter);
+            tokio::time::sleep(Duration::from_secs_f64(wait.max(1.0))).await;
+        }
+    }
+
+    async fn claim_once(&self, args: &ClaimArgs, confirm: bool) -> Result<(), Error> {
+        // MI: offline claims are built for the --authority wallet, which also pays the fees
+        let (pubkey, fee_payer) = if self.offline.is_some() {
+            (self.authority()?, self.authority()?)
+        } else {
+            self.check_signer_is_authority()?;
+            (self.signer()?.pubkey(), self.fee_payer()?.pubkey())
+        };
+        let proof = require_proof(&self.rpc_client, pubkey).await?;
         let mut ixs = vec![];
-        let beneficiary = match args.to {
-            None => self.initialize_ata(pubkey).await,
+        let beneficiary = match args.to.as_ref() {
+            None if self.offline.is_some() => {
+                let tokens = spl_associated_token_account::get_associated_token_address(
+                    &pubkey,
+                    &MINT_ADDRESS,
+                );
+                ixs.push(
+                    spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                        &fee_payer,
+                        &pubkey,
+                        &MINT_ADDRESS,
+                        &spl_token::id(),
+                    ),
+                );
+                tokens
+            }
+            None => self.initialize_ata(pubkey).await?,
             Some(to) => {
                 // Create beneficiary token account, if needed
-                let wallet = Pubkey::from_str(&to).expect("Failed to parse wallet address");
+                let wallet = Pubkey::from_str(to).expect("Failed to parse wallet address");
                 let benefiary_tokens = spl_associated_token_account::get_associated_token_address(
                     &wallet,
                     &MINT_ADDRESS,
@@ -37,7 +119,7 @@ impl Miner {
                 {
                     ixs.push(
                         spl_associated_token_account::instruction::create_associated_token_account(
-                            &pubkey,
+                            &fee_payer,
                             &wallet,
                             &ore_api::consts::MINT_ADDRESS,
                             &spl_token::id(),
@@ -48,38 +130,56 @@ impl Miner {
             }
         };
 
-        // Parse amount to claim
-        let amount = if let Some(amount) = args.amount {
-            amount_f64_to_u64(amount)
-        } else {
-            proof.balance
+        // Parse amount to claim, the exact base units win over the decimal amount
+        let amount = match (args.amount_raw, args.amount) {
+            (Some(raw), Some(amount)) if raw.ne(&amount_f64_to_u64(amount)) => {
+                return Err(Error::Failed(format!(
+                    "--amount-raw {} does not match AMOUNT {}",
+                    raw, amount
+                )));
+            }
+            (Some(raw), _) => raw,
+            (None, Some(amount)) => amount_f64_to_u64(amount),
+            (None, None) => proof.balance,
         };
+        if amount.gt(&proof.balance) {
+            return Err(Error::Failed(format!(
+                "Cannot claim {} ORE, only {} ORE is claimable",
+                amount_u64_to_string(amount),
+                amount_u64_to_string(proof.balance)
+            )));
+        }
 
         // Confirm user wants to claim
-        if !ask_confirm(
-            format!(
-                "\nYou are about to claim {}.\n\nAre you sure you want to continue? [Y/n]",
+        if confirm
+            && !ask_confirm(
                 format!(
-                    "{} ORE",
-                    amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS)
+                    "\nYou are about to claim {}.{}\n\nAre you sure you want to continue? [Y/n]",
+                    format!("{} ORE", amount_u64_to_string(amount)).bold(),
+                    memo_line(self.memo.as_deref()),
                 )
-                .bold(),
+                .as_str(),
             )
-            .as_str(),
-        ) {
-            return;
+        {
+            return Ok(());
         }
 
         // Send and confirm
         ixs.push(ore_api::instruction::claim(pubkey, beneficiary, amount));
+        self.push_memo(&mut ixs, &fee_payer)?;
+        if let Some(path) = self.offline.as_ref() {
+            return self
+                .write_offline_transaction(&ixs, CU_LIMIT_CLAIM, fee_payer, path)
+                .await;
+        }
         self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false, None)
-            .await
-            .ok();
+            .await?;
+        Ok(())
     }
 
-    async fn initialize_ata(&self, wallet: Pubkey) -> Pubkey {
+    async fn initialize_ata(&self, wallet: Pubkey) -> Result<Pubkey, Error> {
         // Initialize client.
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
 
         // Build instructions.
@@ -90,20 +190,19 @@ impl Miner {
 
         // Check if ata already exists
         if let Ok(Some(_ata)) = client.get_token_account(&token_account_pubkey).await {
-            return token_account_pubkey;
+            return Ok(token_account_pubkey);
         }
         // Sign and send transaction.
         let ix = spl_associated_token_account::instruction::create_associated_token_account(
-            &signer.pubkey(),
+            &self.fee_payer()?.pubkey(),
             &signer.pubkey(),
             &ore_api::consts::MINT_ADDRESS,
             &spl_token::id(),
         );
         self.send_and_confirm(&[ix], ComputeBudget::Fixed(400_000), false, None)
-            .await
-            .ok();
+            .await?;
 
         // Return token account address
-        token_account_pubkey
+        Ok(token_account_pubkey)
     }
 }
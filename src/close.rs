@@ -1,38 +1,42 @@
 use colored::*;
 use solana_sdk::signature::Signer;
-use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::ClaimArgs,
     send_and_confirm::ComputeBudget,
-    utils::{ask_confirm, get_proof_with_authority},
-    Miner,
+    utils::{amount_u64_to_string, ask_confirm, require_proof},
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn close(&self) {
+    pub async fn close(&self) -> Result<(), Error> {
         // Confirm proof exists
-        let signer = self.signer();
-        let proof = get_proof_with_authority(&self.rpc_client, signer.pubkey()).await;
+        let signer = self.signer()?;
+        let proof = require_proof(&self.rpc_client, signer.pubkey()).await?;
 
         // Confirm the user wants to close.
         if !ask_confirm(
             format!("{} You have {} ORE staked in this account.\nAre you sure you want to {}close this account? [Y/n]", 
                 "WARNING".yellow(),
-                amount_to_ui_amount(proof.balance, ore_api::consts::TOKEN_DECIMALS),
+                amount_u64_to_string(proof.balance),
                 if proof.balance.gt(&0) { "claim your stake and "} else { "" }
             ).as_str()
         ) {
-            return;
+            return Ok(());
         }
 
         // Claim stake
         if proof.balance.gt(&0) {
             self.claim(ClaimArgs {
                 amount: None,
+                amount_raw: None,
                 to: None,
+                schedule: None,
+                threshold: 0.0,
+                jitter: 0,
+                yes: false,
             })
-            .await;
+            .await?;
         }
 
         // Submit close transaction
@@ -40,5 +44,6 @@ impl Miner {
         self.send_and_confirm(&[ix], ComputeBudget::Fixed(500_000), false, None)
             .await
             .ok();
+        Ok(())
     }
 }
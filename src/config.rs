@@ -1,10 +1,13 @@
+use std::time::Duration;
+
+use chrono::Local;
 use colored::Colorize;
 use ore_api::consts::EPOCH_DURATION;
 
-use crate::{utils, Miner};
+use crate::{args::ConfigArgs, utils, Error, Miner};
 
 impl Miner {
-    pub async fn config(&self) {
+    pub async fn config(&self, args: ConfigArgs) -> Result<(), Error> {
         let config = utils::get_config(&self.rpc_client).await;
         println!("{}: {}", "Last reset at".bold(), config.last_reset_at);
         println!("{}: {}", "Min difficulty".bold(), config.min_difficulty);
@@ -15,5 +18,46 @@ impl Miner {
             utils::amount_u64_to_string(config.top_balance)
         );
         println!("{}: {} sec", "Epoch time".bold(), EPOCH_DURATION);
+        if !args.watch {
+            return Ok(());
+        }
+
+        // MI: epoch resets and difficulty adjustments explain rewards shifting mid-session
+        println!(
+            "\nWatching for changes every {} sec...",
+            args.watch_interval.max(1)
+        );
+        let mut last = config;
+        loop {
+            tokio::time::sleep(Duration::from_secs(args.watch_interval.max(1))).await;
+            let config = utils::get_config(&self.rpc_client).await;
+            let mut changes = vec![];
+            if config.last_reset_at.ne(&last.last_reset_at) {
+                changes.push(format!(
+                    "Epoch reset at {} (was {})",
+                    config.last_reset_at, last.last_reset_at
+                ));
+            }
+            if config.min_difficulty.ne(&last.min_difficulty) {
+                changes.push(format!(
+                    "Min difficulty: {} -> {}",
+                    last.min_difficulty, config.min_difficulty
+                ));
+            }
+            if config.base_reward_rate.ne(&last.base_reward_rate) {
+                changes.push(format!(
+                    "Base reward rate: {} -> {}",
+                    last.base_reward_rate, config.base_reward_rate
+                ));
+            }
+            if !changes.is_empty() {
+                println!(
+                    "[{}] {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    changes.join(", ")
+                );
+            }
+            last = config;
+        }
     }
 }
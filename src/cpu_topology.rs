@@ -0,0 +1,171 @@
+use core_affinity::CoreId;
+
+// MI: logical cpu ids of the performance cores on hybrid CPUs, where the platform exposes them.
+// Linux lists them in sysfs for Intel hybrid parts; other platforms return None.
+pub fn performance_cpu_ids() -> Option<Vec<usize>> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/sys/devices/cpu_core/cpus")
+            .ok()
+            .and_then(|list| parse_cpu_list(&list))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+// MI: logical cpu ids that share a physical core with the cpu, itself included, where the
+// platform exposes them. Linux lists them in sysfs; other platforms return None.
+pub fn thread_siblings(cpu_id: usize) -> Option<Vec<usize>> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string(format!(
+            "/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+            cpu_id
+        ))
+        .ok()
+        .and_then(|list| parse_cpu_list(&list))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = cpu_id;
+        None
+    }
+}
+
+// The logical cpus to pin hashing threads to, threads_per_core for each selected core. Every
+// thread gets its own SMT sibling of the core, so no two threads share a logical cpu. A
+// selected cpu that is a sibling of an earlier one is the same physical core and is skipped.
+pub fn sibling_cores(
+    selected: &[CoreId],
+    threads_per_core: usize,
+    siblings: impl Fn(usize) -> Option<Vec<usize>>,
+) -> Result<Vec<CoreId>, String> {
+    if threads_per_core.le(&1) {
+        return Ok(selected.to_vec());
+    }
+    let mut used: Vec<usize> = vec![];
+    let mut cores = vec![];
+    for core_id in selected {
+        if used.contains(&core_id.id) {
+            continue;
+        }
+        let mut cpu_ids = siblings(core_id.id).ok_or_else(|| {
+            "SMT siblings cannot be detected on this platform, use --threads instead of --threads-per-core".to_string()
+        })?;
+        // Start with the selected cpu itself
+        cpu_ids.retain(|id| id.ne(&core_id.id));
+        cpu_ids.insert(0, core_id.id);
+        if cpu_ids.len().lt(&threads_per_core) {
+            return Err(format!(
+                "Core {} has {} hardware threads, fewer than --threads-per-core {}",
+                core_id.id,
+                cpu_ids.len(),
+                threads_per_core
+            ));
+        }
+        cores.extend(
+            cpu_ids
+                .iter()
+                .take(threads_per_core)
+                .map(|&id| CoreId { id }),
+        );
+        used.extend(cpu_ids);
+    }
+    Ok(cores)
+}
+
+// Parse a kernel cpu list such as "0-7,16,18-19"
+pub fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut ids = vec![];
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                ids.extend(start..=end);
+            }
+            None => ids.push(part.trim().parse().ok()?),
+        }
+    }
+    Some(ids)
+}
+
+// Keep only the selected cores that are performance cores
+pub fn performance_cores(selected: &[CoreId], performance_cpu_ids: &[usize]) -> Vec<CoreId> {
+    selected
+        .iter()
+        .filter(|core_id| performance_cpu_ids.contains(&core_id.id))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core_ids(ids: &[usize]) -> Vec<CoreId> {
+        ids.iter().map(|&id| CoreId { id }).collect()
+    }
+
+    fn ids(core_ids: &[CoreId]) -> Vec<usize> {
+        core_ids.iter().map(|core_id| core_id.id).collect()
+    }
+
+    #[test]
+    fn parses_kernel_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3\n"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(parse_cpu_list("0-1,4,6-7"), Some(vec![0, 1, 4, 6, 7]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("0-x"), None);
+    }
+
+    #[test]
+    fn keeps_the_performance_cores_of_a_hybrid_cpu() {
+        // i7-12700: 8 performance cores with 2 threads each on cpus 0-15, 4 efficiency cores on 16-19
+        let performance_cpu_ids = parse_cpu_list("0-15").unwrap();
+        let selected = core_ids(&(0..20).collect::<Vec<_>>());
+        let performance = performance_cores(&selected, &performance_cpu_ids);
+        assert_eq!(ids(&performance), (0..16).collect::<Vec<_>>());
+    }
+
+    // 4 cores with 2 hardware threads each, enumerated like Intel: cpu n and n + 4 are siblings
+    fn smt_siblings(cpu_id: usize) -> Option<Vec<usize>> {
+        let core = cpu_id % 4;
+        Some(vec![core, core + 4])
+    }
+
+    #[test]
+    fn pins_each_thread_to_its_own_sibling() {
+        let cores = sibling_cores(&core_ids(&[0, 1]), 2, smt_siblings).unwrap();
+        assert_eq!(ids(&cores), vec![0, 4, 1, 5]);
+        // cpu 4 is the second hardware thread of core 0, which is already used
+        let cores = sibling_cores(&core_ids(&[0, 4, 2]), 2, smt_siblings).unwrap();
+        assert_eq!(ids(&cores), vec![0, 4, 2, 6]);
+        let cores = sibling_cores(&core_ids(&[5]), 2, smt_siblings).unwrap();
+        assert_eq!(ids(&cores), vec![5, 1]);
+    }
+
+    #[test]
+    fn rejects_more_threads_than_siblings() {
+        assert!(sibling_cores(&core_ids(&[0]), 3, smt_siblings).is_err());
+        assert!(sibling_cores(&core_ids(&[0]), 2, |_| None).is_err());
+        // One thread per core needs no sibling information
+        let cores = sibling_cores(&core_ids(&[0, 4]), 1, |_| None).unwrap();
+        assert_eq!(ids(&cores), vec![0, 4]);
+    }
+
+    #[test]
+    fn keeps_only_selected_cores() {
+        let performance_cpu_ids = parse_cpu_list("0-7").unwrap();
+        let selected = core_ids(&[2, 6, 10, 12]);
+        assert_eq!(
+            ids(&performance_cores(&selected, &performance_cpu_ids)),
+            vec![2, 6]
+        );
+        // Efficiency cores only, the caller falls back to the selection
+        let selected = core_ids(&[10, 12]);
+        assert!(performance_cores(&selected, &performance_cpu_ids).is_empty());
+    }
+}
@@ -2,3 +2,5 @@ pub const CU_LIMIT_UPGRADE: u32 = 20_000;
 pub const CU_LIMIT_CLAIM: u32 = 32_000;
 pub const _CU_LIMIT_RESET: u32 = 12_200;
 pub const _CU_LIMIT_MINE: u32 = 3200;
+pub const CU_LIMIT_LOOKUP_TABLE: u32 = 100_000;
+pub const CU_LIMIT_MAX: u32 = 1_400_000;
@@ -0,0 +1,233 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::*;
+use ore_api::{consts::EPOCH_DURATION, state::Config};
+use ore_utils::AccountDeserialize;
+use solana_program::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    sysvar,
+};
+use solana_sdk::{
+    clock::Clock,
+    signature::{read_keypair_file, Signer},
+};
+
+use crate::{
+    args::DoctorArgs,
+    send_and_confirm::MIN_SOL_BALANCE,
+    utils::{proof_pubkey, redact_url, redact_urls},
+    Error, Miner,
+};
+
+const MAX_CLOCK_LAG: i64 = 10;
+
+impl Miner {
+    pub async fn doctor(&self, args: DoctorArgs) -> Result<(), Error> {
+        let mut failures = 0;
+
+        // RPC reachability and version
+        match self.rpc_client.get_version().await {
+            Ok(version) => check(
+                true,
+                "RPC",
+                format!(
+                    "{} (solana-core {})",
+                    redact_url(&self.rpc_client.url()),
+                    version.solana_core
+                ),
+            ),
+            Err(err) => {
+                failures += 1;
+                check(
+                    false,
+                    "RPC",
+                    redact_urls(&format!("{} ({})", self.rpc_client.url(), err)),
+                );
+            }
+        }
+
+        // Clock sysvar lag
+        let clock = match self.rpc_client.get_account_data(&sysvar::clock::ID).await {
+            Ok(data) => bincode::deserialize::<Clock>(&data).ok(),
+            Err(_) => None,
+        };
+        match clock.as_ref() {
+            Some(clock) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as i64;
+                let lag = now.saturating_sub(clock.unix_timestamp);
+                let ok = lag.abs().le(&MAX_CLOCK_LAG);
+                if !ok {
+                    failures += 1;
+                }
+                check(
+                    ok,
+                    "Clock",
+                    format!("sysvar lags local time by {} sec", lag),
+                );
+            }
+            None => {
+                failures += 1;
+                check(false, "Clock", "failed to fetch clock sysvar".to_string());
+            }
+        }
+
+        // Signer and fee payer keypairs
+        let signer = self
+            .keypair_filepath
+            .as_ref()
+            .and_then(|filepath| read_keypair_file(filepath).ok());
+        match signer.as_ref() {
+            Some(signer) => check(true, "Keypair", signer.pubkey().to_string()),
+            None => {
+                failures += 1;
+                check(
+                    false,
+                    "Keypair",
+                    format!("no keypair found at {:?}", self.keypair_filepath),
+                );
+            }
+        }
+        let fee_payer = self
+            .fee_payer_filepath
+            .as_ref()
+            .and_then(|filepath| read_keypair_file(filepath).ok());
+
+        // Fee payer balance
+        match fee_payer.as_ref() {
+            Some(fee_payer) => match self.rpc_client.get_balance(&fee_payer.pubkey()).await {
+                Ok(balance) => {
+                    let ok = balance.gt(&sol_to_lamports(MIN_SOL_BALANCE));
+                    if !ok {
+                        failures += 1;
+                    }
+                    check(
+                        ok,
+                        "Fee payer",
+                        format!("{} ({} SOL)", fee_payer.pubkey(), lamports_to_sol(balance)),
+                    );
+                }
+                Err(err) => {
+                    failures += 1;
+                    check(false, "Fee payer", err.to_string());
+                }
+            },
+            None => {
+                failures += 1;
+                check(
+                    false,
+                    "Fee payer",
+                    format!("no keypair found at {:?}", self.fee_payer_filepath),
+                );
+            }
+        }
+
+        // Proof account
+        if signer.is_some() || self.authority.is_some() {
+            let proof_address = proof_pubkey(self.authority()?);
+            // A new wallet has no proof yet, that is expected and not a failure
+            if self.rpc_client.get_account(&proof_address).await.is_ok() {
+                check(true, "Proof", proof_address.to_string());
+            } else {
+                note(
+                    "Proof",
+                    format!(
+                        "{} not found, it will be opened on first mine",
+                        proof_address
+                    ),
+                );
+            }
+        }
+
+        // Config epoch status
+        let config = match self
+            .rpc_client
+            .get_account_data(&ore_api::consts::CONFIG_ADDRESS)
+            .await
+        {
+            Ok(data) => Config::try_from_bytes(&data).ok().copied(),
+            Err(_) => None,
+        };
+        match (config, clock) {
+            (Some(config), Some(clock)) => {
+                let next_reset_at = config.last_reset_at.saturating_add(EPOCH_DURATION);
+                check(
+                    true,
+                    "Config",
+                    if next_reset_at.le(&clock.unix_timestamp) {
+                        format!(
+                            "epoch ended {} sec ago, awaiting reset",
+                            clock.unix_timestamp.saturating_sub(next_reset_at)
+                        )
+                    } else {
+                        format!(
+                            "next reset in {} sec",
+                            next_reset_at.saturating_sub(clock.unix_timestamp)
+                        )
+                    },
+                );
+            }
+            _ => {
+                failures += 1;
+                check(
+                    false,
+                    "Config",
+                    "failed to fetch program config".to_string(),
+                );
+            }
+        }
+
+        // Dynamic fee endpoint
+        if self.dynamic_fee {
+            match self.dynamic_fee().await {
+                Ok((fee, provider)) => check(
+                    true,
+                    "Dynamic fee",
+                    format!("{} microlamports from {}", fee, provider),
+                ),
+                Err(err) => {
+                    failures += 1;
+                    check(false, "Dynamic fee", err);
+                }
+            }
+        }
+
+        // CPU cores
+        let num_cores = num_cpus::get() as u64;
+        match args.cores {
+            Some(cores) => {
+                let ok = cores.le(&num_cores);
+                if !ok {
+                    failures += 1;
+                }
+                check(
+                    ok,
+                    "Cores",
+                    format!("{} requested, {} available", cores, num_cores),
+                );
+            }
+            None => check(true, "Cores", format!("{} available", num_cores)),
+        }
+
+        if failures.gt(&0) {
+            return Err(Error::Failed(format!("{} check(s) failed", failures)));
+        }
+        println!("\n{} All checks passed", "OK".bold().green());
+        Ok(())
+    }
+}
+
+fn check(ok: bool, label: &str, detail: String) {
+    let status = if ok {
+        "OK".bold().green()
+    } else {
+        "FAIL".bold().red()
+    };
+    println!("  {:>4} {}: {}", status, label.bold(), detail);
+}
+
+fn note(label: &str, detail: String) {
+    println!("  {:>4} {}: {}", "NOTE".bold(), label.bold(), detail);
+}
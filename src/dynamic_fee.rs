@@ -1,5 +1,8 @@
 use crate::Miner;
 
+use cached::{Cached, TimedCache};
+use clap::ValueEnum;
+
 use ore_api::consts::BUS_ADDRESSES;
 use reqwest::Client;
 use serde_json::{json, Value};
@@ -11,31 +14,26 @@ use solana_client::rpc_response::RpcPrioritizationFee;
 
 use url::Url;
 
-pub const DEFAULT_PRIORITY_FEE: u64 = 10_000;
+// Seconds a fee estimate is reused before the providers are asked again
+pub const DYNAMIC_FEE_CACHE_TTL: u64 = 5;
+
+// MI: the last estimate and its provider, reused until --dynamic-fee-cache-ttl expires
+pub type DynamicFeeCache = TimedCache<(), (u64, &'static str)>;
 
-enum FeeStrategy {
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeStrategy {
     Helius,
     Triton,
+    #[value(name = "local")]
     LOCAL,
     Alchemy,
     Quiknode,
 }
 
-impl Miner {
-    pub async fn dynamic_fee(&self) -> Result<u64, String> {
-        // Get url
-        let rpc_url = self
-            .dynamic_fee_url
-            .clone()
-            .unwrap_or(self.rpc_client.url());
-
-        // Select fee estiamte strategy
-        let host = Url::parse(&rpc_url)
-            .unwrap()
-            .host_str()
-            .unwrap()
-            .to_string();
-        let strategy = if host.contains("helius-rpc.com") {
+impl FeeStrategy {
+    // Pick the strategy from the fee estimate url host
+    pub fn detect(host: &str) -> Self {
+        if host.contains("helius-rpc.com") {
             FeeStrategy::Helius
         } else if host.contains("alchemy.com") {
             FeeStrategy::Alchemy
@@ -45,8 +43,91 @@ impl Miner {
             FeeStrategy::Triton
         } else {
             FeeStrategy::LOCAL
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeeStrategy::Helius => "Helius",
+            FeeStrategy::Triton => "Triton",
+            FeeStrategy::LOCAL => "local RPC",
+            FeeStrategy::Alchemy => "Alchemy",
+            FeeStrategy::Quiknode => "QuickNode",
+        }
+    }
+}
+
+impl Miner {
+    // MI: try each fee estimate provider in order, the first successful estimate wins.
+    // Returns the fee along with the name of the provider that supplied it.
+    pub async fn dynamic_fee(&self) -> Result<(u64, &'static str), String> {
+        self.dynamic_fee_with_cache()
+            .await
+            .map(|(fee, provider, _)| (self.cap_dynamic_fee(fee), provider))
+    }
+
+    // The estimate before cap_dynamic_fee, also telling whether it came from the cache
+    pub async fn dynamic_fee_with_cache(&self) -> Result<(u64, &'static str, bool), String> {
+        let cached = self
+            .dynamic_fee_cache
+            .lock()
+            .unwrap()
+            .cache_get(&())
+            .copied();
+        if let Some((fee, provider)) = cached {
+            return Ok((fee, provider, true));
+        }
+        let (fee, provider) = self.dynamic_fee_estimate().await?;
+        self.dynamic_fee_cache
+            .lock()
+            .unwrap()
+            .cache_set((), (fee, provider));
+        Ok((fee, provider, false))
+    }
+
+    // Drop the cached estimate, so the next transaction asks the providers again
+    pub fn invalidate_dynamic_fee_cache(&self) {
+        self.dynamic_fee_cache.lock().unwrap().cache_clear();
+    }
+
+    // The raw estimate, before the buffer and the priority fee cap are applied
+    pub async fn dynamic_fee_estimate(&self) -> Result<(u64, &'static str), String> {
+        // Get urls
+        let rpc_urls = if self.dynamic_fee_url.is_empty() {
+            vec![self.rpc_client.url()]
+        } else {
+            self.dynamic_fee_url.clone()
         };
 
+        let mut errors = vec![];
+        for (i, rpc_url) in rpc_urls.into_iter().enumerate() {
+            // Select fee estiamte strategy, an explicit --dynamic-fee-strategy wins over the url host
+            let strategy = self
+                .dynamic_fee_strategy
+                .get(i)
+                .copied()
+                .unwrap_or_else(|| {
+                    let host = Url::parse(&rpc_url)
+                        .ok()
+                        .and_then(|url| url.host_str().map(|host| host.to_string()))
+                        .unwrap_or_default();
+                    FeeStrategy::detect(&host)
+                });
+            match self.dynamic_fee_from(rpc_url, strategy).await {
+                Ok(fee) => return Ok((fee, strategy.name())),
+                Err(err) => errors.push(err),
+            }
+        }
+        Err(errors.join("; "))
+    }
+
+    async fn dynamic_fee_from(
+        &self,
+        rpc_url: String,
+        strategy: FeeStrategy,
+    ) -> Result<u64, String> {
+        let provider = strategy.name();
+
         // Build fee estimate request
         let client = Client::new();
         let ore_addresses: Vec<String> = std::iter::once(ore_api::ID.to_string())
@@ -112,14 +193,14 @@ impl Miner {
             // split json from send
             // 1) handle response
             let Ok(resp) = client.post(rpc_url).json(&body).send().await else {
-                eprintln!("didn't get dynamic fee estimate, use default instead.");
-                return Ok(DEFAULT_PRIORITY_FEE);
+                return Err(format!("Didn't get dynamic fee estimate from {provider}."));
             };
 
             // 2) handle json
             let Ok(response) = resp.json::<Value>().await else {
-                eprintln!("didn't get json data from fee estimate response, use default instead.");
-                return Ok(DEFAULT_PRIORITY_FEE);
+                return Err(format!(
+                    "Didn't get json data from {provider} fee estimate response."
+                ));
             };
 
             response
@@ -132,58 +213,51 @@ impl Miner {
             FeeStrategy::Helius => response["result"]["priorityFeeEstimate"]
                 .as_f64()
                 .map(|fee| fee as u64)
-                .ok_or_else(|| format!("Failed to parse priority fee response: {:?}", response)),
+                .ok_or_else(|| {
+                    format!("Failed to parse {provider} priority fee response: {:?}", response)
+                }),
             FeeStrategy::Quiknode => response["result"]["per_compute_unit"]["medium"]
                 .as_f64()
                 .map(|fee| fee as u64)
                 .ok_or_else(|| format!("Please enable the Solana Priority Fee API add-on in your QuickNode account.")),
-            FeeStrategy::Alchemy => response["result"]
-                .as_array()
-                .and_then(|arr| {
-                    Some(
-                        arr.into_iter()
-                            .map(|v| v["prioritizationFee"].as_u64().unwrap())
-                            .collect::<Vec<u64>>(),
-                    )
-                })
-                .and_then(|fees| {
-                    Some(
-                        ((fees.iter().sum::<u64>() as f32 / fees.len() as f32).ceil() * 1.2) as u64,
-                    )
-                })
-                .ok_or_else(|| format!("Failed to parse priority fee response: {:?}", response)),
+            FeeStrategy::Alchemy => parse_alchemy_fee(&response).ok_or_else(|| {
+                format!("Failed to parse {provider} priority fee response: {:?}", response)
+            }),
             FeeStrategy::Triton => {
                 serde_json::from_value::<Vec<RpcPrioritizationFee>>(response["result"].clone())
-                    .map(|prioritization_fees| {
-                        estimate_prioritization_fee_micro_lamports(prioritization_fees)
+                    .map_err(|error| {
+                        format!(
+                            "Failed to parse {provider} priority fee response: {response:?}, error: {error}"
+                        )
                     })
-                    .or_else(|error: serde_json::Error| {
-                        Err(format!(
-                            "Failed to parse priority fee response: {response:?}, error: {error}"
-                        ))
+                    .and_then(|prioritization_fees| {
+                        // MI: an empty result falls through to the next provider
+                        estimate_prioritization_fee_micro_lamports(prioritization_fees)
+                            .ok_or_else(|| {
+                                format!("{provider} returned no prioritization fees")
+                            })
                     })
             },
             FeeStrategy::LOCAL => {
                 self.local_dynamic_fee().await.or_else(|err| {
-                    Err(format!("Failed to parse priority fee response: {err}"))
+                    Err(format!("Failed to parse {provider} priority fee response: {err}"))
                 })
             },
         };
 
-        // Check if the calculated fee is higher than max
-        match calculated_fee {
-            Err(err) => Err(err),
-            Ok(fee) => {
-                if let Some(max_fee) = self.priority_fee_cap {
-                    // MI vanilla
-                    // Ok(fee.min(max_fee))
-                    Ok((fee + 5000).min(max_fee)) // add extra 5000 microlamports as buffer
-                } else {
-                    // MI vanilla
-                    // Ok(fee)
-                    Ok(fee + 5000)
-                }
-            }
+        calculated_fee
+    }
+
+    // Check if the calculated fee is higher than max
+    pub fn cap_dynamic_fee(&self, fee: u64) -> u64 {
+        if let Some(max_fee) = self.priority_fee_cap {
+            // MI vanilla
+            // fee.min(max_fee)
+            (fee + 5000).min(max_fee) // add extra 5000 microlamports as buffer
+        } else {
+            // MI vanilla
+            // fee
+            fee + 5000
         }
     }
 
@@ -237,10 +311,23 @@ impl Miner {
     }
 }
 
-/// Our estimate is the average over the last 20 slots
+/// Alchemy returns recent prioritization fees per slot, we take the average plus a 20% margin
+pub fn parse_alchemy_fee(response: &Value) -> Option<u64> {
+    let fees = response["result"]
+        .as_array()?
+        .iter()
+        .map(|v| v["prioritizationFee"].as_u64())
+        .collect::<Option<Vec<u64>>>()?;
+    if fees.is_empty() {
+        return None;
+    }
+    Some(((fees.iter().sum::<u64>() as f64 / fees.len() as f64).ceil() * 1.2) as u64)
+}
+
+/// Our estimate is the average over the last 20 slots, None without any fees
 pub fn estimate_prioritization_fee_micro_lamports(
     prioritization_fees: Vec<RpcPrioritizationFee>,
-) -> u64 {
+) -> Option<u64> {
     let prioritization_fees = prioritization_fees
         .into_iter()
         .rev()
@@ -252,11 +339,77 @@ pub fn estimate_prioritization_fee_micro_lamports(
         )
         .collect::<Vec<_>>();
     if prioritization_fees.is_empty() {
-        panic!("Response does not contain any prioritization fees");
+        return None;
     }
 
     let prioritization_fee =
         prioritization_fees.iter().sum::<u64>() / prioritization_fees.len() as u64;
 
-    prioritization_fee
+    Some(prioritization_fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // getRecentPrioritizationFees over the ORE accounts, as returned by Alchemy
+    const ALCHEMY_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "result": [
+            { "slot": 290317510, "prioritizationFee": 0 },
+            { "slot": 290317511, "prioritizationFee": 12500 },
+            { "slot": 290317512, "prioritizationFee": 25000 },
+            { "slot": 290317513, "prioritizationFee": 10001 }
+        ],
+        "id": 1
+    }"#;
+
+    #[test]
+    fn parses_the_alchemy_fee() {
+        let response: Value = serde_json::from_str(ALCHEMY_RESPONSE).unwrap();
+        // Average 11875.25, rounded up to 11876, plus 20%
+        assert_eq!(parse_alchemy_fee(&response), Some(14251));
+    }
+
+    #[test]
+    fn rejects_an_unexpected_alchemy_response() {
+        let empty = json!({ "jsonrpc": "2.0", "result": [], "id": 1 });
+        assert_eq!(parse_alchemy_fee(&empty), None);
+        let error = json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32600, "message": "Invalid request" },
+            "id": 1
+        });
+        assert_eq!(parse_alchemy_fee(&error), None);
+        let malformed = json!({ "jsonrpc": "2.0", "result": [{ "slot": 1 }], "id": 1 });
+        assert_eq!(parse_alchemy_fee(&malformed), None);
+    }
+
+    #[test]
+    fn averages_the_triton_fees_and_rejects_an_empty_result() {
+        let fees = (0..25)
+            .map(|slot| RpcPrioritizationFee {
+                slot,
+                prioritization_fee: if slot < 5 { 1_000_000 } else { 1_000 },
+            })
+            .collect();
+        // Only the last 20 slots count
+        assert_eq!(
+            estimate_prioritization_fee_micro_lamports(fees),
+            Some(1_000)
+        );
+        assert_eq!(estimate_prioritization_fee_micro_lamports(vec![]), None);
+    }
+
+    #[test]
+    fn detects_alchemy_from_the_host() {
+        assert_eq!(
+            FeeStrategy::detect("solana-mainnet.g.alchemy.com"),
+            FeeStrategy::Alchemy
+        );
+        assert_eq!(
+            FeeStrategy::detect("api.mainnet-beta.solana.com"),
+            FeeStrategy::LOCAL
+        );
+    }
 }
@@ -0,0 +1,45 @@
+use std::fmt;
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+
+use crate::send_and_confirm::{send_error, SendError};
+
+// MI: why a command stopped. The ore binary prints it and picks the exit code, library
+// callers can match on it.
+#[derive(Debug)]
+pub enum Error {
+    // No keypair is configured, or the file could not be read
+    Keypair(String),
+    // The fee payer cannot pay for the transaction
+    InsufficientFunds(String),
+    // The command failed, e.g. its transaction did not land
+    Failed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Keypair(err) | Error::InsufficientFunds(err) | Error::Failed(err) => {
+                write!(f, "{}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ClientError> for Error {
+    fn from(err: ClientError) -> Self {
+        match send_error(&err) {
+            Some(SendError::InsufficientBalance(err)) => Error::InsufficientFunds(err.clone()),
+            _ => Error::Failed(err.to_string()),
+        }
+    }
+}
+
+// Lets send_and_confirm and the other RPC helpers use `?` on the keypair loaders
+impl From<Error> for ClientError {
+    fn from(err: Error) -> Self {
+        ClientErrorKind::Custom(err.to_string()).into()
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use serde_json::json;
+
+// Exit codes. Argument errors exit with 2 from clap.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_INSUFFICIENT_FUNDS: i32 = 3;
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+// MI: how fatal errors are printed and which command they belong to, set once from
+// --error-format before the command runs
+static ERROR_OUTPUT: OnceLock<(ErrorFormat, String)> = OnceLock::new();
+
+pub fn init(format: ErrorFormat, command: &str) {
+    let _ = ERROR_OUTPUT.set((format, command.to_string()));
+}
+
+pub fn is_json() -> bool {
+    ERROR_OUTPUT
+        .get()
+        .is_some_and(|(format, _)| format.eq(&ErrorFormat::Json))
+}
+
+// Print the error to stderr, as a JSON object with --error-format json, and exit with the code
+pub fn exit_with_error(error: &str, code: i32) -> ! {
+    match ERROR_OUTPUT.get() {
+        Some((ErrorFormat::Json, command)) => eprintln!(
+            "{}",
+            json!({
+                "command": command,
+                "error": error,
+                "code": code,
+            })
+        ),
+        _ => eprintln!("error: {}", error),
+    }
+    std::process::exit(code);
+}
@@ -0,0 +1,162 @@
+use serde::Serialize;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::broadcast,
+};
+
+// MI: per-round events streamed as newline delimited JSON to dashboards
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MinerEvent {
+    RoundStart {
+        challenge: String,
+        cutoff_time: u64,
+    },
+    SolutionFound {
+        difficulty: u32,
+        hashes: u64,
+        nonce: u64,
+    },
+    SubmissionResult {
+        ok: bool,
+        signature: Option<String>,
+        error: Option<String>,
+    },
+    Balance {
+        balance: f64,
+        change: f64,
+    },
+}
+
+// Events are dropped when no client is connected, or for clients that fall behind
+pub struct EventStream {
+    sender: Option<broadcast::Sender<String>>,
+}
+
+impl EventStream {
+    pub fn disabled() -> Self {
+        Self { sender: None }
+    }
+
+    // Listen on a TCP address (host:port) or, on unix, a socket path
+    pub async fn listen(addr: &str) -> Result<Self, String> {
+        let (sender, _) = broadcast::channel::<String>(256);
+        if is_host_port(addr) {
+            let socket_addr = tokio::net::lookup_host(addr)
+                .await
+                .map_err(|err| format!("Failed to resolve {}: {}", addr, err))?
+                .next()
+                .ok_or_else(|| format!("{} did not resolve to an address", addr))?;
+            let listener = tokio::net::TcpListener::bind(socket_addr)
+                .await
+                .map_err(|err| err.to_string())?;
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(forward_events(stream, sender.subscribe()));
+                }
+            });
+        } else {
+            #[cfg(unix)]
+            {
+                remove_stale_socket(addr)?;
+                let listener =
+                    tokio::net::UnixListener::bind(addr).map_err(|err| err.to_string())?;
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    while let Ok((stream, _)) = listener.accept().await {
+                        tokio::spawn(forward_events(stream, sender.subscribe()));
+                    }
+                });
+            }
+            #[cfg(not(unix))]
+            return Err(format!("{} is not a host:port address", addr));
+        }
+        Ok(Self {
+            sender: Some(sender),
+        })
+    }
+
+    pub fn emit(&self, event: MinerEvent) {
+        if let Some(sender) = self.sender.as_ref() {
+            if let Ok(line) = serde_json::to_string(&event) {
+                let _ = sender.send(line);
+            }
+        }
+    }
+}
+
+// MI: host:port, or [ipv6]:port. Anything with a path separator is a socket path.
+fn is_host_port(addr: &str) -> bool {
+    if addr.contains('/') {
+        return false;
+    }
+    match addr.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+// MI: a socket left behind by an earlier run is replaced, any other file is never deleted
+#[cfg(unix)]
+fn remove_stale_socket(path: &str) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            std::fs::remove_file(path).map_err(|err| format!("Failed to remove {}: {}", path, err))
+        }
+        Ok(_) => Err(format!("{} exists and is not a socket", path)),
+        Err(err) if err.kind().eq(&std::io::ErrorKind::NotFound) => Ok(()),
+        Err(err) => Err(format!("Failed to inspect {}: {}", path, err)),
+    }
+}
+
+async fn forward_events<W: AsyncWrite + Unpin>(
+    mut stream: W,
+    mut receiver: broadcast::Receiver<String>,
+) {
+    loop {
+        match receiver.recv().await {
+            Ok(line) => {
+                if stream
+                    .write_all(format!("{}\n", line).as_bytes())
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_addresses_from_socket_paths() {
+        assert!(is_host_port("127.0.0.1:9000"));
+        assert!(is_host_port("localhost:9000"));
+        assert!(is_host_port("[::1]:9000"));
+        assert!(!is_host_port("/tmp/ore-events.sock"));
+        assert!(!is_host_port("./events:9000"));
+        assert!(!is_host_port("events.sock"));
+        assert!(!is_host_port("localhost:port"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn never_removes_a_file_that_is_not_a_socket() {
+        let path = std::env::temp_dir().join(format!("ore-cli-events-{}", std::process::id()));
+        std::fs::write(&path, "keypair").unwrap();
+        let path = path.to_string_lossy().to_string();
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(std::path::Path::new(&path).exists());
+        std::fs::remove_file(&path).unwrap();
+        assert!(remove_stale_socket(&path).is_ok());
+    }
+}
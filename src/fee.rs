@@ -0,0 +1,90 @@
+use colored::*;
+use serde_json::json;
+use solana_program::native_token::lamports_to_sol;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    args::FeeArgs,
+    send_and_confirm::{clamp_priority_fee, max_cu_price, transaction_fee},
+    Error, Miner, OutputFormat,
+};
+
+impl Miner {
+    // MI: show the priority fee a mine transaction would pay, without sending anything
+    pub async fn fee(&self, args: FeeArgs) -> Result<(), Error> {
+        let num_signatures = if self.signer()?.pubkey() == self.fee_payer()?.pubkey() {
+            1
+        } else {
+            2
+        };
+        let max_priority_fee = self
+            .max_total_fee_lamports
+            .map(|max_total_fee| max_cu_price(max_total_fee, num_signatures, args.compute_budget));
+
+        let estimate = self.dynamic_fee_estimate().await;
+        let (raw_fee, capped_fee, provider, error) = match &estimate {
+            Ok((fee, provider)) => (
+                Some(*fee),
+                Some(self.cap_dynamic_fee(*fee)),
+                Some(*provider),
+                None,
+            ),
+            Err(err) => (None, None, None, Some(err.clone())),
+        };
+        let fee = match capped_fee {
+            Some(fee) if self.dynamic_fee => fee,
+            _ => self.priority_fee.unwrap_or(0),
+        };
+        let cu_price = clamp_priority_fee(fee, max_priority_fee);
+        let total_fee = transaction_fee(num_signatures, args.compute_budget, cu_price);
+
+        match self.output {
+            OutputFormat::Json => {
+                println!(
+                    "{}",
+                    json!({
+                        "dynamic_fee": self.dynamic_fee,
+                        "provider": provider,
+                        "raw_estimate": raw_fee,
+                        "capped_estimate": capped_fee,
+                        "estimate_error": error,
+                        "static_priority_fee": self.priority_fee,
+                        "priority_fee_cap": self.priority_fee_cap,
+                        "max_priority_fee": max_priority_fee,
+                        "cu_price": cu_price,
+                        "compute_budget": args.compute_budget,
+                        "total_fee_lamports": total_fee,
+                        "total_fee_sol": lamports_to_sol(total_fee),
+                    })
+                );
+            }
+            OutputFormat::Text => {
+                match (raw_fee, capped_fee, provider) {
+                    (Some(raw_fee), Some(capped_fee), Some(provider)) => {
+                        println!("Provider: {}", provider);
+                        println!("Raw estimate: {} microlamports", raw_fee);
+                        println!("With buffer and cap: {} microlamports", capped_fee);
+                    }
+                    _ => println!(
+                        "{} {}",
+                        "WARNING".bold().yellow(),
+                        error.unwrap_or_default()
+                    ),
+                }
+                if !self.dynamic_fee {
+                    println!(
+                        "Dynamic fees are off, using the static priority fee: {} microlamports",
+                        self.priority_fee.unwrap_or(0)
+                    );
+                }
+                if let Some(max_priority_fee) = max_priority_fee {
+                    println!("Max total fee limit: {} microlamports", max_priority_fee);
+                }
+                println!("Compute unit price: {} microlamports", cu_price);
+                println!("Compute budget: {} CUs", args.compute_budget);
+                println!("Total fee: {} SOL", lamports_to_sol(total_fee));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+// MI: one-line fixes for the errors new users run into most
+struct Hint {
+    patterns: &'static [&'static str],
+    message: &'static str,
+    example: &'static str,
+}
+
+const HINTS: &[Hint] = &[
+    Hint {
+        patterns: &[
+            "insufficient balance",
+            "insufficient funds",
+            "insufficient lamports",
+            "no record of a prior credit",
+        ],
+        message: "The fee payer needs more SOL to pay transaction fees.",
+        example: "solana transfer <FEE_PAYER_ADDRESS> 0.1",
+    },
+    Hint {
+        patterns: &["no proof account"],
+        message: "This wallet has no proof account yet. Mining opens one.",
+        example: "ore mine --cores 1",
+    },
+    Hint {
+        patterns: &["blockhash not found", "max blockhash refreshes"],
+        message: "The transaction expired before it landed. The RPC may be lagging or congested.",
+        example: "ore --rpc <FASTER_RPC_URL> --priority-fee 50000 mine",
+    },
+    Hint {
+        patterns: &["no keypair found", "no fee payer keypair found"],
+        message: "Point --keypair at your wallet file, or set a default with the Solana CLI.",
+        example: "solana config set --keypair ~/.config/solana/id.json",
+    },
+];
+
+pub const EXAMPLES: &str = "\
+Examples:
+  Check your setup before mining
+    ore doctor
+  Mine on 4 cores with dynamic priority fees
+    ore --dynamic-fee --dynamic-fee-url <RPC_URL> mine --cores 4
+  Mine on devnet after requesting an airdrop
+    ore --cluster devnet airdrop 1
+    ore --cluster devnet mine --cores 1
+  Claim rewards to your wallet
+    ore claim
+  Stake the ORE in your wallet for a rewards multiplier
+    ore stake
+  Check balances and the current reward rates
+    ore balance
+    ore rewards";
+
+// The error as a hint and example command when it is a known one. The raw error is kept
+// with --verbose.
+pub fn with_hint(err: &str, verbose: bool) -> String {
+    let lowercase = err.to_lowercase();
+    let Some(hint) = HINTS.iter().find(|hint| {
+        hint.patterns
+            .iter()
+            .any(|pattern| lowercase.contains(pattern))
+    }) else {
+        return err.to_string();
+    };
+    let hint = format!("{} Try: `{}`", hint.message, hint.example);
+    if verbose {
+        format!("{}\n  Hint: {}", err, hint)
+    } else {
+        hint
+    }
+}
@@ -0,0 +1,541 @@
+//! The ore-cli miner as a library, so bots and GUIs can mine, claim, stake and fetch
+//! accounts without shelling out to the `ore` binary. Build a [`Miner`] with
+//! [`Miner::builder`] and call the command methods with their [`args`]. Commands return an
+//! [`Error`] instead of exiting the process.
+
+mod airdrop;
+pub mod args;
+mod balance;
+mod benchmark;
+mod busses;
+mod claim;
+mod close;
+mod config;
+mod cpu_topology;
+mod cu_limits;
+mod doctor;
+pub mod dynamic_fee;
+pub mod error;
+mod events;
+mod fee;
+pub mod hints;
+#[cfg(feature = "admin")]
+mod initialize;
+mod lookup_table;
+mod mine;
+mod nonce_file;
+mod notify_test;
+mod offline;
+mod open;
+mod proof;
+mod rate_limit;
+mod records;
+mod report;
+mod rewards;
+pub mod rpc_sender;
+pub mod send_and_confirm;
+mod stake;
+mod transfer;
+mod upgrade;
+pub mod utils;
+mod version;
+mod whoami;
+
+use std::{
+    sync::{atomic::AtomicBool, Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use cached::TimedCache;
+use clap::ValueEnum;
+use colored::*;
+use dynamic_fee::{DynamicFeeCache, FeeStrategy, DYNAMIC_FEE_CACHE_TTL};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentLevel,
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+};
+
+pub use error::Error;
+
+pub struct Miner {
+    pub keypair_filepath: Option<String>,
+    pub authority: Option<Pubkey>,
+    pub priority_fee: Option<u64>,
+    pub priority_fee_cap: Option<u64>,
+    pub dynamic_fee_url: Vec<String>,
+    pub dynamic_fee: bool,
+    pub dynamic_fee_strategy: Vec<FeeStrategy>,
+    pub dynamic_fee_cache: Mutex<DynamicFeeCache>,
+    pub rpc_client: Arc<RpcClient>,
+    pub fee_payer_filepath: Option<String>,
+    pub slack_webhook: Option<String>,
+    pub discord_webhook: Option<String>,
+    pub no_sound_notification: bool,
+    pub on_solution: Option<String>,
+    pub explorer_url: String,
+    pub webhook_min_interval: u64,
+    pub max_total_fee_lamports: Option<u64>,
+    pub verbose: bool,
+    pub output: OutputFormat,
+    pub commitment: CommitmentLevel,
+    pub offline: Option<String>,
+    pub last_fee_lamports: RwLock<u64>,
+    pub last_send_timings: RwLock<send_and_confirm::SendTimings>,
+    pub last_fee_capped: RwLock<bool>,
+    pub broadcast_clients: Vec<Arc<RpcClient>>,
+    pub confirm_timeout: Option<Duration>,
+    pub memo: Option<String>,
+    pub priority_fee_escalate: Option<u64>,
+    pub lookup_table: Option<Pubkey>,
+    pub priority_fee_auto: bool,
+    pub shutdown: Arc<AtomicBool>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+// MI: configures a Miner for embedding, starting from the command line defaults
+pub struct MinerBuilder(Miner);
+
+impl MinerBuilder {
+    pub fn keypair_filepath(mut self, keypair_filepath: Option<String>) -> Self {
+        self.0.keypair_filepath = keypair_filepath;
+        self
+    }
+
+    pub fn authority(mut self, authority: Option<Pubkey>) -> Self {
+        self.0.authority = authority;
+        self
+    }
+
+    pub fn priority_fee(mut self, priority_fee: Option<u64>) -> Self {
+        self.0.priority_fee = priority_fee;
+        self
+    }
+
+    pub fn priority_fee_cap(mut self, priority_fee_cap: Option<u64>) -> Self {
+        self.0.priority_fee_cap = priority_fee_cap;
+        self
+    }
+
+    pub fn dynamic_fee_url(mut self, dynamic_fee_url: Vec<String>) -> Self {
+        self.0.dynamic_fee_url = dynamic_fee_url;
+        self
+    }
+
+    pub fn dynamic_fee(mut self, dynamic_fee: bool) -> Self {
+        self.0.dynamic_fee = dynamic_fee;
+        self
+    }
+
+    pub fn dynamic_fee_strategy(mut self, dynamic_fee_strategy: Vec<FeeStrategy>) -> Self {
+        self.0.dynamic_fee_strategy = dynamic_fee_strategy;
+        self
+    }
+
+    // Seconds to reuse a dynamic fee estimate, 0 asks the providers for every transaction
+    pub fn dynamic_fee_cache_ttl(mut self, dynamic_fee_cache_ttl: u64) -> Self {
+        self.0.dynamic_fee_cache = Mutex::new(TimedCache::with_lifespan(dynamic_fee_cache_ttl));
+        self
+    }
+
+    pub fn fee_payer_filepath(mut self, fee_payer_filepath: Option<String>) -> Self {
+        self.0.fee_payer_filepath = fee_payer_filepath;
+        self
+    }
+
+    pub fn slack_webhook(mut self, slack_webhook: Option<String>) -> Self {
+        self.0.slack_webhook = slack_webhook;
+        self
+    }
+
+    pub fn discord_webhook(mut self, discord_webhook: Option<String>) -> Self {
+        self.0.discord_webhook = discord_webhook;
+        self
+    }
+
+    pub fn no_sound_notification(mut self, no_sound_notification: bool) -> Self {
+        self.0.no_sound_notification = no_sound_notification;
+        self
+    }
+
+    pub fn on_solution(mut self, on_solution: Option<String>) -> Self {
+        self.0.on_solution = on_solution;
+        self
+    }
+
+    pub fn explorer_url(mut self, explorer_url: String) -> Self {
+        self.0.explorer_url = explorer_url;
+        self
+    }
+
+    pub fn webhook_min_interval(mut self, webhook_min_interval: u64) -> Self {
+        self.0.webhook_min_interval = webhook_min_interval;
+        self
+    }
+
+    pub fn max_total_fee_lamports(mut self, max_total_fee_lamports: Option<u64>) -> Self {
+        self.0.max_total_fee_lamports = max_total_fee_lamports;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.0.verbose = verbose;
+        self
+    }
+
+    pub fn output(mut self, output: OutputFormat) -> Self {
+        self.0.output = output;
+        self
+    }
+
+    pub fn commitment(mut self, commitment: CommitmentLevel) -> Self {
+        self.0.commitment = commitment;
+        self
+    }
+
+    pub fn offline(mut self, offline: Option<String>) -> Self {
+        self.0.offline = offline;
+        self
+    }
+
+    pub fn broadcast_clients(mut self, broadcast_clients: Vec<Arc<RpcClient>>) -> Self {
+        self.0.broadcast_clients = broadcast_clients;
+        self
+    }
+
+    pub fn confirm_timeout(mut self, confirm_timeout: Option<Duration>) -> Self {
+        self.0.confirm_timeout = confirm_timeout;
+        self
+    }
+
+    pub fn memo(mut self, memo: Option<String>) -> Self {
+        self.0.memo = memo;
+        self
+    }
+
+    pub fn priority_fee_escalate(mut self, priority_fee_escalate: Option<u64>) -> Self {
+        self.0.priority_fee_escalate = priority_fee_escalate;
+        self
+    }
+
+    pub fn lookup_table(mut self, lookup_table: Option<Pubkey>) -> Self {
+        self.0.lookup_table = lookup_table;
+        self
+    }
+
+    pub fn priority_fee_auto(mut self, priority_fee_auto: bool) -> Self {
+        self.0.priority_fee_auto = priority_fee_auto;
+        self
+    }
+
+    // Set to finish the current mining round and return, e.g. from a signal handler
+    pub fn shutdown(mut self, shutdown: Arc<AtomicBool>) -> Self {
+        self.0.shutdown = shutdown;
+        self
+    }
+
+    pub fn build(self) -> Miner {
+        self.0
+    }
+}
+
+impl Miner {
+    // The original constructor, the other options keep their builder defaults
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rpc_client: Arc<RpcClient>,
+        priority_fee: Option<u64>,
+        priority_fee_cap: Option<u64>,
+        keypair_filepath: Option<String>,
+        dynamic_fee_url: Vec<String>,
+        dynamic_fee: bool,
+        fee_payer_filepath: Option<String>,
+        slack_webhook: Option<String>,
+        discord_webhook: Option<String>,
+        no_sound_notification: bool,
+    ) -> Self {
+        Self::builder(rpc_client)
+            .priority_fee(priority_fee)
+            .priority_fee_cap(priority_fee_cap)
+            .keypair_filepath(keypair_filepath)
+            .dynamic_fee_url(dynamic_fee_url)
+            .dynamic_fee(dynamic_fee)
+            .fee_payer_filepath(fee_payer_filepath)
+            .slack_webhook(slack_webhook)
+            .discord_webhook(discord_webhook)
+            .no_sound_notification(no_sound_notification)
+            .build()
+    }
+
+    // A miner with the command line defaults: a 100,000 microlamport fee cap, confirmed
+    // commitment, text output and solscan links. Set at least the keypair before signing.
+    pub fn builder(rpc_client: Arc<RpcClient>) -> MinerBuilder {
+        MinerBuilder(Self {
+            keypair_filepath: None,
+            authority: None,
+            priority_fee: None,
+            priority_fee_cap: Some(100_000),
+            dynamic_fee_url: vec![],
+            dynamic_fee: false,
+            dynamic_fee_strategy: vec![],
+            dynamic_fee_cache: Mutex::new(TimedCache::with_lifespan(DYNAMIC_FEE_CACHE_TTL)),
+            rpc_client,
+            fee_payer_filepath: None,
+            slack_webhook: None,
+            discord_webhook: None,
+            no_sound_notification: false,
+            on_solution: None,
+            explorer_url: "https://solscan.io/tx".to_string(),
+            webhook_min_interval: 0,
+            max_total_fee_lamports: None,
+            verbose: false,
+            output: OutputFormat::Text,
+            commitment: CommitmentLevel::Confirmed,
+            offline: None,
+            last_fee_lamports: RwLock::new(0),
+            last_send_timings: RwLock::new(Default::default()),
+            last_fee_capped: RwLock::new(false),
+            broadcast_clients: vec![],
+            confirm_timeout: None,
+            memo: None,
+            priority_fee_escalate: None,
+            lookup_table: None,
+            priority_fee_auto: false,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn signer(&self) -> Result<Keypair, Error> {
+        match self.keypair_filepath.as_ref() {
+            Some(filepath) => read_keypair_file(filepath).map_err(|err| {
+                Error::Keypair(format!("No keypair found at {}: {}", filepath, err))
+            }),
+            None => Err(Error::Keypair("No keypair provided".to_string())),
+        }
+    }
+
+    // The proof authority defaults to the signer
+    pub fn authority(&self) -> Result<Pubkey, Error> {
+        match self.authority {
+            Some(authority) => Ok(authority),
+            None => Ok(self.signer()?.pubkey()),
+        }
+    }
+
+    // MI: read-only commands query the authority without requiring a keypair, so they can run
+    // on a machine that has none
+    pub fn query_authority(&self) -> Result<Pubkey, Error> {
+        if let Some(authority) = self.authority {
+            return Ok(authority);
+        }
+        self.keypair_filepath
+            .as_ref()
+            .and_then(|filepath| read_keypair_file(filepath).ok())
+            .map(|signer| signer.pubkey())
+            .ok_or_else(|| {
+                Error::Keypair(
+                    "No keypair is configured. Provide an address, or pass --authority or --keypair."
+                        .to_string(),
+                )
+            })
+    }
+
+    // Claim and stake derive the proof from the signer, so it must be the authority
+    pub fn check_signer_is_authority(&self) -> Result<(), Error> {
+        let signer = self.signer()?.pubkey();
+        let authority = self.authority()?;
+        if authority.ne(&signer) {
+            return Err(Error::Failed(format!(
+                "Signer {} is not the proof authority {}",
+                signer, authority
+            )));
+        }
+        Ok(())
+    }
+
+    // MI: when the signer pays the fees, fees are drawn from the wallet rewards are claimed to
+    pub fn check_fee_payer_separate(&self, required: bool) -> Result<(), Error> {
+        let signer = self.signer()?.pubkey();
+        if self.fee_payer()?.pubkey().ne(&signer) {
+            return Ok(());
+        }
+        if required {
+            return Err(Error::Failed(format!(
+                "The fee payer is the signer {}. Pass a different wallet with --fee-payer.",
+                signer
+            )));
+        }
+        println!(
+            "{} The fee payer is the signer {}, so fees and claimed rewards share a wallet. Use --fee-payer to pay fees from another wallet.",
+            "NOTICE".bold(),
+            signer
+        );
+        Ok(())
+    }
+
+    // MI: attach the --memo, as long as the transaction still fits in a packet
+    pub fn push_memo(&self, ixs: &mut Vec<Instruction>, payer: &Pubkey) -> Result<(), Error> {
+        let Some(memo) = self.memo.as_ref() else {
+            return Ok(());
+        };
+        ixs.push(utils::memo_ix(memo));
+        let size = utils::transaction_size(ixs, payer);
+        if size.gt(&PACKET_DATA_SIZE) {
+            return Err(Error::Failed(format!(
+                "The memo makes the transaction {} bytes, over the {} byte limit. Shorten it by {} bytes.",
+                size,
+                PACKET_DATA_SIZE,
+                size - PACKET_DATA_SIZE
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn explorer_link(&self, sig: &Signature) -> String {
+        format!("{}/{}", self.explorer_url.trim_end_matches('/'), sig)
+    }
+
+    pub fn fee_payer(&self) -> Result<Keypair, Error> {
+        match self.fee_payer_filepath.as_ref() {
+            Some(filepath) => read_keypair_file(filepath).map_err(|err| {
+                Error::Keypair(format!(
+                    "No fee payer keypair found at {}: {}",
+                    filepath, err
+                ))
+            }),
+            None => Err(Error::Keypair("No fee payer keypair provided".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc_sender::mock::keypair_file;
+
+    fn rpc_client() -> Arc<RpcClient> {
+        Arc::new(RpcClient::new_mock("succeeds".to_string()))
+    }
+
+    #[test]
+    fn builder_has_the_command_line_defaults() {
+        let miner = Miner::builder(rpc_client()).build();
+        assert_eq!(miner.priority_fee_cap, Some(100_000));
+        assert_eq!(miner.priority_fee, None);
+        assert_eq!(miner.commitment, CommitmentLevel::Confirmed);
+        assert_eq!(miner.output, OutputFormat::Text);
+        assert_eq!(miner.explorer_url, "https://solscan.io/tx");
+        assert!(miner.keypair_filepath.is_none());
+        assert!(miner.signer().is_err());
+    }
+
+    #[test]
+    fn builder_setters_override_the_defaults() {
+        let miner = Miner::builder(rpc_client())
+            .priority_fee(Some(5_000))
+            .priority_fee_cap(None)
+            .commitment(CommitmentLevel::Finalized)
+            .output(OutputFormat::Json)
+            .keypair_filepath(Some("id.json".to_string()))
+            .dynamic_fee(true)
+            .verbose(true)
+            .build();
+        assert_eq!(miner.priority_fee, Some(5_000));
+        assert_eq!(miner.priority_fee_cap, None);
+        assert_eq!(miner.commitment, CommitmentLevel::Finalized);
+        assert_eq!(miner.output, OutputFormat::Json);
+        assert_eq!(miner.keypair_filepath.as_deref(), Some("id.json"));
+        assert!(miner.dynamic_fee);
+        assert!(miner.verbose);
+    }
+
+    #[test]
+    fn new_matches_the_builder() {
+        let new = Miner::new(
+            rpc_client(),
+            Some(5_000),
+            Some(50_000),
+            Some("id.json".to_string()),
+            vec!["https://fees.example.com".to_string()],
+            true,
+            Some("payer.json".to_string()),
+            Some("https://hooks.slack.com/x".to_string()),
+            None,
+            true,
+        );
+        let built = Miner::builder(rpc_client())
+            .priority_fee(Some(5_000))
+            .priority_fee_cap(Some(50_000))
+            .keypair_filepath(Some("id.json".to_string()))
+            .dynamic_fee_url(vec!["https://fees.example.com".to_string()])
+            .dynamic_fee(true)
+            .fee_payer_filepath(Some("payer.json".to_string()))
+            .slack_webhook(Some("https://hooks.slack.com/x".to_string()))
+            .no_sound_notification(true)
+            .build();
+        assert_eq!(new.priority_fee, built.priority_fee);
+        assert_eq!(new.priority_fee_cap, built.priority_fee_cap);
+        assert_eq!(new.keypair_filepath, built.keypair_filepath);
+        assert_eq!(new.dynamic_fee_url, built.dynamic_fee_url);
+        assert_eq!(new.dynamic_fee, built.dynamic_fee);
+        assert_eq!(new.fee_payer_filepath, built.fee_payer_filepath);
+        assert_eq!(new.slack_webhook, built.slack_webhook);
+        assert_eq!(new.discord_webhook, built.discord_webhook);
+        assert_eq!(new.no_sound_notification, built.no_sound_notification);
+        // The options new does not take keep the builder defaults
+        assert_eq!(new.commitment, built.commitment);
+        assert_eq!(new.output, built.output);
+        assert_eq!(new.explorer_url, built.explorer_url);
+    }
+
+    #[test]
+    fn authority_defaults_to_the_signer() {
+        let signer = Keypair::new();
+        let miner = Miner::builder(rpc_client())
+            .keypair_filepath(Some(keypair_file(&signer)))
+            .build();
+        assert_eq!(miner.authority().unwrap(), signer.pubkey());
+        assert!(miner.check_signer_is_authority().is_ok());
+
+        let miner = Miner::builder(rpc_client())
+            .keypair_filepath(Some(keypair_file(&signer)))
+            .authority(Some(signer.pubkey()))
+            .build();
+        assert!(miner.check_signer_is_authority().is_ok());
+    }
+
+    #[test]
+    fn query_authority_needs_no_keypair_with_an_authority() {
+        let authority = Pubkey::new_unique();
+        let miner = Miner::builder(rpc_client())
+            .authority(Some(authority))
+            .build();
+        assert_eq!(miner.query_authority().unwrap(), authority);
+
+        let miner = Miner::builder(rpc_client()).build();
+        assert!(matches!(miner.query_authority(), Err(Error::Keypair(_))));
+    }
+
+    #[test]
+    fn proof_commands_reject_a_signer_that_is_not_the_authority() {
+        let (signer, authority) = (Keypair::new(), Pubkey::new_unique());
+        let miner = Miner::builder(rpc_client())
+            .keypair_filepath(Some(keypair_file(&signer)))
+            .authority(Some(authority))
+            .build();
+        assert_eq!(miner.authority().unwrap(), authority);
+        assert_eq!(miner.signer().unwrap().pubkey(), signer.pubkey());
+        assert!(matches!(
+            miner.check_signer_is_authority(),
+            Err(Error::Failed(_))
+        ));
+    }
+}
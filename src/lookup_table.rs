@@ -0,0 +1,89 @@
+use colored::*;
+use ore_api::consts::{
+    BUS_ADDRESSES, CONFIG_ADDRESS, MINT_ADDRESS, TREASURY_ADDRESS, TREASURY_TOKENS_ADDRESS,
+};
+use solana_program::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    sysvar,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signer};
+
+use crate::{
+    args::LookupTableArgs, cu_limits::CU_LIMIT_LOOKUP_TABLE, send_and_confirm::ComputeBudget,
+    utils::proof_pubkey, Error, Miner,
+};
+
+impl Miner {
+    // MI: a lookup table of the accounts every mine transaction references, so a versioned
+    // transaction carries 1 byte per account instead of 32
+    pub async fn lookup_table(&self, _args: LookupTableArgs) -> Result<(), Error> {
+        let signer = self.signer()?;
+        let fee_payer = self.fee_payer()?;
+        let recent_slot = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await
+            .map_err(|err| Error::Failed(format!("Failed to fetch a recent slot: {}", err)))?;
+        let (create_ix, address) =
+            create_lookup_table(signer.pubkey(), fee_payer.pubkey(), recent_slot);
+        let mut addresses = BUS_ADDRESSES.to_vec();
+        addresses.extend([
+            CONFIG_ADDRESS,
+            MINT_ADDRESS,
+            TREASURY_ADDRESS,
+            TREASURY_TOKENS_ADDRESS,
+            spl_token::id(),
+            sysvar::instructions::id(),
+            sysvar::slot_hashes::id(),
+            // MI: the proof mined into is the --authority one, not necessarily the signer's
+            proof_pubkey(self.authority()?),
+        ]);
+        let extend_ix = extend_lookup_table(
+            address,
+            signer.pubkey(),
+            Some(fee_payer.pubkey()),
+            addresses,
+        );
+        self.send_and_confirm(
+            &[create_ix, extend_ix],
+            ComputeBudget::Fixed(CU_LIMIT_LOOKUP_TABLE),
+            false,
+            None,
+        )
+        .await?;
+        println!("Lookup table: {}", address);
+        println!("Mine with it by passing --lookup-table {}", address);
+        Ok(())
+    }
+
+    // The --lookup-table account, or None to send legacy transactions
+    pub async fn load_lookup_table(&self) -> Option<AddressLookupTableAccount> {
+        let key = self.lookup_table?;
+        let table = self
+            .rpc_client
+            .get_account(&key)
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|account| {
+                AddressLookupTable::deserialize(&account.data)
+                    .map(|table| table.addresses.to_vec())
+                    .map_err(|err| err.to_string())
+            });
+        match table {
+            Ok(addresses) => Some(AddressLookupTableAccount { key, addresses }),
+            Err(err) => {
+                println!(
+                    "{} Failed to load lookup table {}: {}. Sending a legacy transaction.",
+                    "WARNING".bold().yellow(),
+                    key,
+                    err
+                );
+                None
+            }
+        }
+    }
+}
@@ -1,55 +1,80 @@
-mod args;
-mod balance;
-mod benchmark;
-mod busses;
-mod claim;
-mod close;
-mod config;
-mod cu_limits;
-mod dynamic_fee;
-#[cfg(feature = "admin")]
-mod initialize;
-mod mine;
-mod open;
-mod proof;
-mod rewards;
-mod send_and_confirm;
-mod stake;
-mod transfer;
-mod upgrade;
-mod utils;
-
-use std::sync::Arc;
-
-use args::*;
+mod error_output;
+mod ore_config;
+mod update_check;
+
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use clap::{
     builder::{
         styling::{AnsiColor, Effects},
         Styles,
     },
-    command, Parser, Subcommand,
+    Arg, ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
+use colored::*;
+use error_output::ErrorFormat;
+use more_cli::{
+    args::*, dynamic_fee::FeeStrategy, hints, rpc_sender::MinerRpcSender, utils, Error, Miner,
+    OutputFormat,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
+use ore_config::OreConfig;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_client::RpcClientConfig};
 use solana_sdk::{
-    commitment_config::CommitmentConfig,
-    signature::{read_keypair_file, Keypair},
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
 };
 
-struct Miner {
-    pub keypair_filepath: Option<String>,
-    pub priority_fee: Option<u64>,
-    pub priority_fee_cap: Option<u64>,
-    pub dynamic_fee_url: Option<String>,
-    pub dynamic_fee: bool,
-    pub rpc_client: Arc<RpcClient>,
-    pub fee_payer_filepath: Option<String>,
-    pub slack_webhook: Option<String>,
-    pub discord_webhook: Option<String>,
-    pub no_sound_notification: bool,
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    fn url(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://localhost:8899",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    fn level(&self) -> CommitmentLevel {
+        match self {
+            Commitment::Processed => CommitmentLevel::Processed,
+            Commitment::Confirmed => CommitmentLevel::Confirmed,
+            Commitment::Finalized => CommitmentLevel::Finalized,
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
+    #[command(
+        about = "Request a SOL airdrop to the fee payer (devnet, testnet and localnet only)"
+    )]
+    Airdrop(AirdropArgs),
+
     #[command(about = "Fetch an account balance")]
     Balance(BalanceArgs),
 
@@ -68,24 +93,51 @@ enum Commands {
     #[command(about = "Fetch the program config")]
     Config(ConfigArgs),
 
+    #[command(about = "Diagnose your mining environment")]
+    Doctor(DoctorArgs),
+
+    #[command(about = "Print the priority fee decision for a mine transaction without sending it")]
+    Fee(FeeArgs),
+
+    #[command(about = "Create a lookup table of the mining accounts, for --lookup-table")]
+    LookupTable(LookupTableArgs),
+
     #[command(about = "Start mining")]
     Mine(MineArgs),
 
+    #[command(about = "Send a test message to the configured Slack and Discord webhooks")]
+    NotifyTest(NotifyTestArgs),
+
     #[command(about = "Fetch a proof account by address")]
     Proof(ProofArgs),
 
+    #[command(about = "Show the best hash ever found by each wallet")]
+    Records(RecordsArgs),
+
     #[command(about = "Fetch the current reward rate for each difficulty level")]
     Rewards(RewardsArgs),
 
+    #[command(about = "Sign a transaction written by --offline, e.g. on an air-gapped machine")]
+    Sign(SignArgs),
+
     #[command(about = "Stake to earn a rewards multiplier")]
     Stake(StakeArgs),
 
+    #[command(about = "Broadcast a transaction signed by `ore sign`")]
+    Submit(SubmitArgs),
+
     #[command(about = "Send ORE to anyone, anywhere in the world.")]
     Transfer(TransferArgs),
 
     #[command(about = "Upgrade your ORE tokens from v1 to v2")]
     Upgrade(UpgradeArgs),
 
+    #[command(about = "Show the wallets and accounts your configuration resolves to")]
+    Whoami(WhoamiArgs),
+
+    #[command(about = "Print build and dependency versions, for bug reports")]
+    Version(VersionArgs),
+
     #[cfg(feature = "admin")]
     #[command(about = "Initialize the program")]
     Initialize(InitializeArgs),
@@ -102,6 +154,14 @@ struct Args {
     )]
     rpc: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "CLUSTER",
+        help = "Well-known cluster to use when --rpc is not given.",
+        global = true
+    )]
+    cluster: Option<Cluster>,
+
     #[clap(
         global = true,
         short = 'C',
@@ -111,6 +171,14 @@ struct Args {
     )]
     config_file: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "ORE_CONFIG_FILEPATH",
+        help = "Filepath to an ore-cli settings file (TOML, or JSON with a .json extension). Command line flags take precedence over its values.",
+        global = true
+    )]
+    ore_config: Option<String>,
+
     #[arg(
         long,
         value_name = "KEYPAIR_FILEPATH",
@@ -127,6 +195,14 @@ struct Args {
     )]
     fee_payer: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "AUTHORITY_ADDRESS",
+        help = "Address of the proof authority, if different from the signer keypair.",
+        global = true
+    )]
+    authority: Option<String>,
+
     #[arg(
         long,
         value_name = "FEE_MICROLAMPORTS",
@@ -145,21 +221,62 @@ struct Args {
     )]
     priority_fee_cap: Option<u64>,
 
+    #[arg(
+        long,
+        value_name = "STEP_MICROLAMPORTS",
+        help = "Raise the priority fee by this step each time a sent transaction fails to confirm and is retried, up to the priority fee cap. Each new transaction starts again from the base fee.",
+        global = true
+    )]
+    priority_fee_auto_escalate: Option<u64>,
+
     #[arg(
         long,
         value_name = "DYNAMIC_FEE_URL",
-        help = "RPC URL to use for dynamic fee estimation.",
+        help = "RPC URL to use for dynamic fee estimation. Repeat to add fallback providers, tried in order. Defaults to ORE_DYNAMIC_FEE_URL, comma separated.",
         global = true
     )]
-    dynamic_fee_url: Option<String>,
+    dynamic_fee_url: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "LAMPORTS",
+        help = "Max total fee per transaction in lamports, including the base fee. Translated into a compute unit price for each transaction's compute budget.",
+        global = true
+    )]
+    max_total_fee_lamports: Option<u64>,
 
     #[arg(long, help = "Enable dynamic priority fees", global = true)]
     dynamic_fee: bool,
 
+    #[arg(
+        long,
+        help = "Use dynamic priority fees when an estimate is available, and the static --priority-fee otherwise. Each round logs which one was used.",
+        conflicts_with = "dynamic_fee",
+        global = true
+    )]
+    priority_fee_auto: bool,
+
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        help = "Fee estimate provider for dynamic fees. Repeat to match each --dynamic-fee-url in order. Detected from the url host if not set.",
+        global = true
+    )]
+    dynamic_fee_strategy: Vec<FeeStrategy>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Reuse a dynamic fee estimate for this many seconds instead of asking the provider for every transaction. The estimate is refreshed after a round fails to land. 0 disables the cache.",
+        default_value = "5",
+        global = true
+    )]
+    dynamic_fee_cache_ttl: u64,
+
     #[arg(
         long,
         value_name = "SLACK_WEBHOOK",
-        help = "slack webhook url to send notification message.",
+        help = "slack webhook url to send notification message. Defaults to ORE_SLACK_WEBHOOK.",
         global = true
     )]
     slack_webhook: Option<String>,
@@ -167,11 +284,20 @@ struct Args {
     #[arg(
         long,
         value_name = "DISCORD_WEBHOOK",
-        help = "discord webhook url to send notification message.",
+        help = "discord webhook url to send notification message. Defaults to ORE_DISCORD_WEBHOOK.",
         global = true
     )]
     discord_webhook: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Minimum interval between webhook notifications. Notifications within the interval are combined into one summary.",
+        default_value = "0",
+        global = true
+    )]
+    webhook_min_interval: u64,
+
     /// Mine with sound notification on/off
     #[arg(
         long,
@@ -182,6 +308,154 @@ struct Args {
     )]
     no_sound_notification: bool,
 
+    #[arg(
+        long,
+        help = "Skip checking crates.io for a newer release at startup.",
+        global = true
+    )]
+    no_update_check: bool,
+
+    #[arg(
+        long,
+        help = "Disable colored output. Also honors the NO_COLOR environment variable.",
+        global = true
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Number of decimals to show for ORE amounts. Defaults to full precision with trailing zeros trimmed.",
+        global = true
+    )]
+    decimals: Option<u8>,
+
+    #[arg(
+        short,
+        long,
+        help = "Print the instructions, compute budget and priority fee of each transaction before sending, and where the time of each mining round went.",
+        global = true
+    )]
+    verbose: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Output format.",
+        default_value = "text",
+        global = true
+    )]
+    output: OutputFormat,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        help = "Format of fatal errors printed to stderr. Json prints an object with the command, error and exit code.",
+        default_value = "text",
+        global = true
+    )]
+    error_format: ErrorFormat,
+
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        help = "Commitment a submitted transaction must reach to count as confirmed. Processed lets the miner start the next round sooner.",
+        default_value = "confirmed",
+        global = true
+    )]
+    commitment: Commitment,
+
+    #[arg(
+        long,
+        value_name = "REQUESTS_PER_SECOND",
+        help = "Pace RPC requests to stay under this rate, for rate limited RPC plans.",
+        global = true
+    )]
+    rpc_rate_limit: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "NETWORK_URL",
+        help = "Also send each transaction to this RPC, for better landing odds during congestion. Repeat to add more. Confirmation still goes through --rpc.",
+        global = true
+    )]
+    broadcast_rpc: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Address lookup table to send mine transactions as versioned transactions, which are smaller. Create one with `ore lookup-table`. Without it, legacy transactions are sent.",
+        global = true
+    )]
+    lookup_table: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Stop resending a transaction after this many seconds. A transaction that is still unconfirmed is checked a few more times before it counts as failed. Defaults to the retry limit.",
+        global = true
+    )]
+    confirm_timeout: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        help = "Attach a memo to transfer and claim transactions, e.g. for exchanges that require one.",
+        global = true
+    )]
+    memo: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FAILURES",
+        help = "Rebuild the RPC connection after this many consecutive connection failures. 0 disables reconnecting.",
+        default_value = "5",
+        global = true
+    )]
+    rpc_reconnect_after: usize,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Give up on an RPC request after this many seconds. Fetch loops that retry on failure retry after the timeout instead of hanging.",
+        default_value = "30",
+        global = true
+    )]
+    rpc_timeout: u64,
+
+    #[arg(
+        long,
+        value_name = "CONNECTIONS",
+        help = "Max idle connections kept open to each RPC host. Defaults to no limit.",
+        global = true
+    )]
+    rpc_pool_size: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write claim and transfer transactions unsigned to this file instead of sending them. Use --authority for the cold wallet.",
+        global = true
+    )]
+    offline: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Shell command to run after each successful submission. Receives ORE_DIFFICULTY, ORE_REWARD and ORE_SIGNATURE as environment variables.",
+        global = true
+    )]
+    on_solution: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "EXPLORER_URL",
+        help = "Base URL of the block explorer used for transaction links, e.g. https://explorer.solana.com/tx or https://xray.helius.xyz/tx",
+        default_value = "https://solscan.io/tx",
+        global = true
+    )]
+    explorer_url: String,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -189,13 +463,76 @@ struct Args {
 #[tokio::main]
 async fn main() {
     color_eyre::install().unwrap();
-    let args = Args::parse();
+    let matches = Args::command()
+        .arg(
+            Arg::new("help_examples")
+                .long("help-examples")
+                .help("Print example commands for common tasks")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .subcommand_required(false)
+        .get_matches();
+    if matches.get_flag("help_examples") {
+        println!("{}", hints::EXAMPLES);
+        return;
+    }
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    error_output::init(
+        args.error_format,
+        matches.subcommand_name().unwrap_or_default(),
+    );
+
+    // MI: secrets can come from the environment instead of the command line, which leaks into
+    // shell history and process listings. Flags win over the environment, which wins over
+    // the ore-cli settings file.
+    let env_var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+    if args.slack_webhook.is_none() {
+        args.slack_webhook = env_var("ORE_SLACK_WEBHOOK");
+    }
+    if args.discord_webhook.is_none() {
+        args.discord_webhook = env_var("ORE_DISCORD_WEBHOOK");
+    }
+    if args.dynamic_fee_url.is_empty() {
+        args.dynamic_fee_url = env_var("ORE_DYNAMIC_FEE_URL")
+            .map(|urls| {
+                urls.split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    // Layer ore-cli settings file below command line flags
+    if let Some(ore_config) = &args.ore_config {
+        match OreConfig::load(ore_config) {
+            Ok(ore_config) => ore_config.apply(&mut args, &matches),
+            Err(err) => error_output::exit_with_error(
+                &format!("Could not load ore config `{}`: {}", ore_config, err),
+                error_output::EXIT_FAILURE,
+            ),
+        }
+    }
+
+    // Disable colors before any command prints
+    if args.no_color || std::env::var_os("NO_COLOR").is_some() {
+        colored::control::set_override(false);
+    }
+    if let Some(decimals) = args.decimals {
+        utils::set_display_decimals(decimals);
+    }
+    if !args.no_update_check {
+        update_check::spawn_update_check();
+    }
 
     // Load the config file from custom path, the default path, or use default config values
     let cli_config = if let Some(config_file) = &args.config_file {
         solana_cli_config::Config::load(config_file).unwrap_or_else(|_| {
-            eprintln!("error: Could not find config file `{}`", config_file);
-            std::process::exit(1);
+            error_output::exit_with_error(
+                &format!("Could not find config file `{}`", config_file),
+                error_output::EXIT_FAILURE,
+            )
         })
     } else if let Some(config_file) = &*solana_cli_config::CONFIG_FILE {
         solana_cli_config::Config::load(config_file).unwrap_or_default()
@@ -204,111 +541,173 @@ async fn main() {
     };
 
     // Initialize miner.
-    let cluster = args.rpc.unwrap_or(cli_config.json_rpc_url);
+    let cluster = args
+        .rpc
+        .or(args.cluster.map(|cluster| cluster.url().to_string()))
+        .unwrap_or(cli_config.json_rpc_url);
     let default_keypair = args.keypair.unwrap_or(cli_config.keypair_path.clone());
     let fee_payer_filepath = args.fee_payer.unwrap_or(default_keypair.clone());
-    let rpc_client = RpcClient::new_with_commitment(cluster, CommitmentConfig::confirmed());
-
-    let miner = Arc::new(Miner::new(
-        Arc::new(rpc_client),
-        args.priority_fee,
-        args.priority_fee_cap,
-        Some(default_keypair),
-        args.dynamic_fee_url,
-        args.dynamic_fee,
-        Some(fee_payer_filepath),
-        args.slack_webhook,
-        args.discord_webhook,
-        args.no_sound_notification,
-    ));
+    let authority = args.authority.map(|address| {
+        Pubkey::from_str(&address).unwrap_or_else(|_| {
+            error_output::exit_with_error(
+                &format!("Invalid authority address: {}", address),
+                error_output::EXIT_FAILURE,
+            )
+        })
+    });
+    let lookup_table = args.lookup_table.map(|address| {
+        Pubkey::from_str(&address).unwrap_or_else(|_| {
+            error_output::exit_with_error(
+                &format!("Invalid lookup table address: {}", address),
+                error_output::EXIT_FAILURE,
+            )
+        })
+    });
+    // MI: every task sharing this client is paced and reconnected by the same sender
+    let rpc_timeout = Duration::from_secs(args.rpc_timeout.max(1));
+    let rpc_client = RpcClient::new_sender(
+        MinerRpcSender::new(
+            cluster,
+            args.rpc_rate_limit,
+            args.rpc_reconnect_after,
+            rpc_timeout,
+            args.rpc_pool_size,
+        ),
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    );
+    let broadcast_clients = args
+        .broadcast_rpc
+        .into_iter()
+        .map(|url| {
+            Arc::new(RpcClient::new_sender(
+                MinerRpcSender::new(
+                    url,
+                    None,
+                    args.rpc_reconnect_after,
+                    rpc_timeout,
+                    args.rpc_pool_size,
+                ),
+                RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+            ))
+        })
+        .collect();
+
+    let verbose = args.verbose;
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let miner = Arc::new(
+        Miner::builder(Arc::new(rpc_client))
+            .priority_fee(args.priority_fee)
+            .priority_fee_cap(args.priority_fee_cap)
+            .keypair_filepath(Some(default_keypair))
+            .authority(authority)
+            .dynamic_fee_url(args.dynamic_fee_url)
+            .dynamic_fee(args.dynamic_fee || args.priority_fee_auto)
+            .dynamic_fee_strategy(args.dynamic_fee_strategy)
+            .dynamic_fee_cache_ttl(args.dynamic_fee_cache_ttl)
+            .fee_payer_filepath(Some(fee_payer_filepath))
+            .slack_webhook(args.slack_webhook)
+            .discord_webhook(args.discord_webhook)
+            .no_sound_notification(args.no_sound_notification)
+            .on_solution(args.on_solution)
+            .explorer_url(args.explorer_url)
+            .webhook_min_interval(args.webhook_min_interval)
+            .max_total_fee_lamports(args.max_total_fee_lamports)
+            .verbose(args.verbose)
+            .output(args.output)
+            .commitment(args.commitment.level())
+            .offline(args.offline)
+            .broadcast_clients(broadcast_clients)
+            .confirm_timeout(args.confirm_timeout.map(Duration::from_secs))
+            .memo(args.memo)
+            .priority_fee_escalate(args.priority_fee_auto_escalate)
+            .lookup_table(lookup_table)
+            .priority_fee_auto(args.priority_fee_auto)
+            .shutdown(shutdown.clone())
+            .build(),
+    );
 
     // Execute user command.
-    match args.command {
-        Commands::Balance(args) => {
-            miner.balance(args).await;
-        }
-        Commands::Benchmark(args) => {
-            miner.benchmark(args).await;
-        }
-        Commands::Busses(_) => {
-            miner.busses().await;
-        }
-        Commands::Claim(args) => {
-            miner.claim(args).await;
-        }
-        Commands::Close(_) => {
-            miner.close().await;
-        }
-        Commands::Config(_) => {
-            miner.config().await;
-        }
+    let result = match args.command {
+        Commands::Airdrop(args) => miner.airdrop(args).await,
+        Commands::Balance(args) => miner.balance(args).await,
+        Commands::Benchmark(args) => miner.benchmark(args).await,
+        Commands::Busses(_) => miner.busses().await,
+        Commands::Claim(args) => miner.claim(args).await,
+        Commands::Close(_) => miner.close().await,
+        Commands::Config(args) => miner.config(args).await,
+        Commands::Doctor(args) => miner.doctor(args).await,
+        Commands::Fee(args) => miner.fee(args).await,
+        Commands::LookupTable(args) => miner.lookup_table(args).await,
         Commands::Mine(args) => {
-            miner.mine(args).await;
-        }
-        Commands::Proof(args) => {
-            miner.proof(args).await;
-        }
-        Commands::Rewards(_) => {
-            miner.rewards().await;
-        }
-        Commands::Stake(args) => {
-            miner.stake(args).await;
-        }
-        Commands::Transfer(args) => {
-            miner.transfer(args).await;
-        }
-        Commands::Upgrade(args) => {
-            miner.upgrade(args).await;
+            tokio::spawn(shutdown_signal_system(shutdown));
+            miner.mine(args).await
         }
+        Commands::NotifyTest(args) => miner.notify_test(args).await,
+        Commands::Proof(args) => miner.proof(args).await,
+        Commands::Records(args) => miner.records(args).await,
+        Commands::Rewards(_) => miner.rewards().await,
+        Commands::Sign(args) => miner.sign(args).await,
+        Commands::Stake(args) => miner.stake(args).await,
+        Commands::Submit(args) => miner.submit(args).await,
+        Commands::Transfer(args) => miner.transfer(args).await,
+        Commands::Upgrade(args) => miner.upgrade(args).await,
+        Commands::Whoami(_) => miner.whoami().await,
+        Commands::Version(args) => miner.version(args).await,
         #[cfg(feature = "admin")]
-        Commands::Initialize(_) => {
-            miner.initialize().await;
-        }
+        Commands::Initialize(_) => miner.initialize().await,
+    };
+
+    // MI: the library returns its errors, printing them and the exit code are up to the binary
+    if let Err(err) = result {
+        let code = match err {
+            Error::InsufficientFunds(_) => error_output::EXIT_INSUFFICIENT_FUNDS,
+            _ => error_output::EXIT_FAILURE,
+        };
+        // Scripts parsing json errors get the raw error
+        let err = if error_output::is_json() {
+            err.to_string()
+        } else {
+            hints::with_hint(&err.to_string(), verbose)
+        };
+        error_output::exit_with_error(&err, code);
     }
 }
 
-impl Miner {
-    pub fn new(
-        rpc_client: Arc<RpcClient>,
-        priority_fee: Option<u64>,
-        priority_fee_cap: Option<u64>,
-        keypair_filepath: Option<String>,
-        dynamic_fee_url: Option<String>,
-        dynamic_fee: bool,
-        fee_payer_filepath: Option<String>,
-        slack_webhook: Option<String>,
-        discord_webhook: Option<String>,
-        no_sound_notification: bool,
-    ) -> Self {
-        Self {
-            rpc_client,
-            keypair_filepath,
-            priority_fee,
-            priority_fee_cap,
-            dynamic_fee_url,
-            dynamic_fee,
-            fee_payer_filepath,
-            slack_webhook,
-            discord_webhook,
-            no_sound_notification,
+// MI: containers are stopped with SIGTERM, terminals with SIGINT. The first signal lets mine
+// finish the in-flight round, a second one exits immediately.
+async fn shutdown_signal_system(shutdown: Arc<AtomicBool>) {
+    loop {
+        #[cfg(unix)]
+        {
+            let Ok(mut sigterm) =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            else {
+                let _ = tokio::signal::ctrl_c().await;
+                request_shutdown(&shutdown);
+                continue;
+            };
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {},
+                _ = sigterm.recv() => {},
+            }
         }
-    }
-
-    pub fn signer(&self) -> Keypair {
-        match self.keypair_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath.clone())
-                .expect(format!("No keypair found at {}", filepath).as_str()),
-            None => panic!("No keypair provided"),
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
         }
+        request_shutdown(&shutdown);
     }
+}
 
-    pub fn fee_payer(&self) -> Keypair {
-        match self.fee_payer_filepath.clone() {
-            Some(filepath) => read_keypair_file(filepath.clone())
-                .expect(format!("No fee payer keypair found at {}", filepath).as_str()),
-            None => panic!("No fee payer keypair provided"),
-        }
+fn request_shutdown(shutdown: &AtomicBool) {
+    if shutdown.swap(true, Ordering::Relaxed) {
+        println!("Exiting now.");
+        std::process::exit(error_output::EXIT_INTERRUPTED);
     }
+    println!(
+        "{} Finishing the current round before exiting. Signal again to exit now.",
+        "SHUTDOWN".bold().yellow()
+    );
 }
 
 fn styles() -> Styles {
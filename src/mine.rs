@@ -1,16 +1,23 @@
 use crate::utils;
 use std::{
+    collections::VecDeque,
     fmt, io,
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
     time::{Duration, Instant},
 };
 
+use chrono::Local;
 use colored::*;
+use core_affinity::CoreId;
 use drillx::{
     equix::{self},
     Hash, Solution,
 };
+use indicatif::ProgressBar;
 use ore_api::{
     consts::{BUS_ADDRESSES, BUS_COUNT, EPOCH_DURATION},
     state::{Bus, Config, Proof},
@@ -19,26 +26,71 @@ use ore_utils::AccountDeserialize;
 use rand::Rng;
 use serenity::{http::Http, model::webhook::Webhook};
 use slack_messaging::Message as SlackChannelMessage;
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    instruction::Instruction,
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    system_instruction::transfer,
+};
 use solana_rpc_client::spinner;
-use solana_sdk::signer::Signer;
-use tokio::sync::mpsc::{self, UnboundedReceiver};
+use solana_sdk::{
+    clock::{Clock, DEFAULT_MS_PER_SLOT},
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    signer::Signer,
+};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
 
 use crate::{
-    args::MineArgs,
-    send_and_confirm::ComputeBudget,
+    args::{MineArgs, StakeArgs, SubmitStrategy},
+    cpu_topology::{performance_cores, performance_cpu_ids, sibling_cores, thread_siblings},
+    cu_limits::CU_LIMIT_MAX,
+    events::{EventStream, MinerEvent},
+    nonce_file::{NonceBest, NonceFile},
+    records::Records,
+    report::{Reporter, RoundReport},
+    send_and_confirm::{
+        is_needs_reset, is_replaced, unconfirmed_signature, ComputeBudget, SendTimings,
+        MIN_SOL_BALANCE,
+    },
     utils::{
-        amount_u64_to_string, get_clock, get_config, get_updated_proof_with_authority, proof_pubkey,
+        amount_f64_to_u64, amount_u64_to_f64, amount_u64_to_string, get_clock, get_config,
+        get_proof_with_authority, get_updated_proof_with_authority, proof_pubkey, signature_landed,
+        PROOF_UPDATE_TIMEOUT,
     },
-    Miner,
+    Error, Miner,
 };
 
+const MAX_RESET_ATTEMPTS: usize = 3;
+const NONCE_FILE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+const CLOCK_SKEW_CHECK_ROUNDS: u64 = 10;
+const CLOCK_SKEW_LOG_DELTA: u64 = 2;
+const ERROR_REPEAT_INTERVAL: Duration = Duration::from_secs(600);
+const ERROR_DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const BUS_RESET_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// MI: Jito mainnet tip accounts
+const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
 // const NONCE_CHECKPOINT_STEP: u64 = 100; // nonce interval
 // const EXPECTED_MIN_DIFFICULTY: u32 = 18;
 // const RISK_TIME: u64 = 29; // sec
 
 enum ParallelStrategy {
-    Cores(u64),
+    Cores(Vec<CoreId>),
     Threads(u64),
 }
 
@@ -48,12 +100,443 @@ pub struct DifficultyPayload {
     pub extra_fee_difficulty: u32,
     pub extra_fee_percent: u64,
     pub messaging_diff: u32,
+    pub max_priority_fee: Option<u64>,
+    pub replace_if_improves: Option<(Arc<RiskWindowHashing>, u32)>,
+}
+
+// MI: hashing that continues after the best hash so far was submitted, through the risk
+// time or, with --pipeline, until the submission confirms
+#[derive(Default)]
+pub struct RiskWindowHashing {
+    pipeline: bool,
+    best: Mutex<Option<(u32, Solution, [u8; 32])>>,
+    checked_in: AtomicU64,
+    hashes: AtomicU64,
+    stop: AtomicBool,
+}
+
+impl RiskWindowHashing {
+    fn offer(&self, difficulty: u32, nonce: u64, hash: &Hash) {
+        let mut best = self.best.lock().unwrap();
+        let improves = match *best {
+            Some((best_difficulty, ..)) => difficulty.gt(&best_difficulty),
+            None => true,
+        };
+        if improves {
+            *best = Some((
+                difficulty,
+                Solution::new(hash.d, nonce.to_le_bytes()),
+                hash.h,
+            ));
+        }
+    }
+
+    // A thread reached the cutoff without the min difficulty, with this many hashes so far
+    fn check_in(&self, hashes: u64) {
+        self.hashes.fetch_add(hashes, Ordering::Relaxed);
+        self.checked_in.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn best(&self) -> Option<(u32, Solution, [u8; 32])> {
+        *self.best.lock().unwrap()
+    }
+
+    // The best solution, if it beats the given difficulty by at least the margin
+    pub fn improved(&self, difficulty: u32, margin: u32) -> Option<(u32, Solution)> {
+        let (best_difficulty, solution, _) = self.best()?;
+        best_difficulty
+            .ge(&difficulty.saturating_add(margin.max(1)))
+            .then_some((best_difficulty, solution))
+    }
+
+    fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Debug)]
 pub enum RewardsMessage {
-    // Rewards(/* difficulty: */ u32, /* rewards: */ f64, /* balance: */ f64),
-    Rewards(u32, f64, f64),
+    // MI: ORE amounts are raw, formatted with amount_u64_to_string when the message is sent
+    // Rewards(/* difficulty: */ u32, /* rewards: */ u64, /* balance: */ u64, /* tx link: */ String),
+    Rewards(u32, u64, u64, String),
+    // Heartbeat(/* rounds: */ u64, /* rewards: */ u64, /* balance: */ u64, /* hashpower: */ u64),
+    Heartbeat(u64, u64, u64, u64),
+    Alert(String),
+    Error(String),
+}
+
+// MI: rolling window of (fee lamports, reward grains) per round
+struct ProfitGuard {
+    window: usize,
+    ore_price: f64,
+    rounds: VecDeque<(u64, u64)>,
+}
+
+impl ProfitGuard {
+    fn new(window: usize, ore_price: f64) -> Self {
+        Self {
+            window: window.max(1),
+            ore_price,
+            rounds: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, fee: u64, rewards: u64) {
+        self.rounds.push_back((fee, rewards));
+        if self.rounds.len() > self.window {
+            self.rounds.pop_front();
+        }
+    }
+
+    // Value of ORE earned per SOL spent over the window
+    fn ratio(&self) -> f64 {
+        let spent: u64 = self.rounds.iter().map(|(fee, _)| fee).sum();
+        let earned: u64 = self.rounds.iter().map(|(_, rewards)| rewards).sum();
+        if spent.eq(&0) {
+            return f64::INFINITY;
+        }
+        amount_u64_to_f64(earned) * self.ore_price / lamports_to_sol(spent)
+    }
+
+    fn is_unprofitable(&self) -> bool {
+        self.rounds.len().ge(&self.window) && self.ratio().lt(&1.0)
+    }
+}
+
+// MI: long-lived hashing threads, pinned and given solver memory once, fed a job each round
+struct HashingPool {
+    workers: Vec<(u64, std::sync::mpsc::Sender<HashingJob>)>,
+    nonce_file: Option<String>,
+}
+
+// MI: a thread's last searched nonce and best hash, for --nonce-file
+#[derive(Clone, Copy)]
+struct WorkerProgress {
+    nonce: u64,
+    best: Option<NonceBest>,
+}
+
+struct HashingJob {
+    challenge: [u8; 32],
+    cutoff_time: u64,
+    min_difficulty: u32,
+    risk_time: u64,
+    checkpoint_step: u64,
+    progress_interval: Duration,
+    progress_bar: Arc<ProgressBar>,
+    global_best_difficulty: Arc<RwLock<u32>>,
+    risk_window: Option<Arc<RiskWindowHashing>>,
+    earliest_min: bool,
+    start_nonce: u64,
+    progress: Option<Arc<Mutex<WorkerProgress>>>,
+    results: UnboundedSender<(u64, u32, Hash, u64)>,
+}
+
+impl HashingPool {
+    fn new(
+        parallel_strategy: &ParallelStrategy,
+        stack_size: Option<usize>,
+        nonce_file: Option<String>,
+        nice: Option<i32>,
+    ) -> Self {
+        // The cores strategy pins each thread and scores every equix solution of a nonce
+        let workers: Vec<(Option<CoreId>, bool)> = match parallel_strategy {
+            ParallelStrategy::Cores(core_ids) => core_ids
+                .iter()
+                .map(|core_id| (Some(*core_id), true))
+                .collect(),
+            ParallelStrategy::Threads(threads) => (0..*threads).map(|_| (None, false)).collect(),
+        };
+
+        // Partition nonce space by worker
+        let count = workers.len() as u64;
+        let workers = workers
+            .into_iter()
+            .enumerate()
+            .map(|(i, (core_id, all_solutions))| {
+                let (sender, jobs) = std::sync::mpsc::channel();
+                let first_nonce = u64::MAX.saturating_div(count).saturating_mul(i as u64);
+                spawn_hashing_thread(stack_size, move || {
+                    hashing_worker(core_id, i.eq(&0), all_solutions, nice, jobs)
+                });
+                (first_nonce, sender)
+            })
+            .collect();
+        Self {
+            workers,
+            nonce_file,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn find_hash(
+        &self,
+        proof: Proof,
+        cutoff_time: u64,
+        min_difficulty: u32,
+        risk_time: u64,
+        checkpoint_step: u64,
+        progress_interval: Duration,
+        quiet: bool,
+        risk_window: Option<Arc<RiskWindowHashing>>,
+        earliest_min: bool,
+    ) -> (Solution, u64) {
+        // MI: resume the nonces and best hash saved for this challenge
+        let resumed = self
+            .nonce_file
+            .as_deref()
+            .and_then(|path| NonceFile::load(path, &proof.challenge, self.workers.len()));
+        let resumed_best = resumed.as_ref().and_then(|resumed| resumed.best);
+        if let Some(path) = self.nonce_file.as_deref().filter(|_| resumed.is_some()) {
+            println!(
+                "Resuming challenge from {} (difficulty {})",
+                path,
+                resumed_best.map_or(0, |best| best.difficulty)
+            );
+        }
+        let start_nonces: Vec<u64> = match resumed.as_ref() {
+            Some(resumed) => resumed.nonces.clone(),
+            None => self.workers.iter().map(|(nonce, _)| *nonce).collect(),
+        };
+        let progress: Vec<_> = start_nonces
+            .iter()
+            .map(|nonce| {
+                Arc::new(Mutex::new(WorkerProgress {
+                    nonce: *nonce,
+                    best: None,
+                }))
+            })
+            .collect();
+
+        // Dispatch job to each thread
+        let progress_bar = Arc::new(new_mining_progress_bar(quiet));
+        let global_best_difficulty =
+            Arc::new(RwLock::new(resumed_best.map_or(0, |best| best.difficulty)));
+        if let (Some(risk_window), Some(best)) = (risk_window.as_ref(), resumed_best) {
+            let solution = Solution::new(best.digest, best.nonce.to_le_bytes());
+            risk_window.offer(best.difficulty, best.nonce, &solution.to_hash());
+        }
+        progress_bar.set_message("Mining...");
+        let (results_sender, mut results) = mpsc::unbounded_channel();
+        for (i, (_, worker)) in self.workers.iter().enumerate() {
+            let _ = worker.send(HashingJob {
+                challenge: proof.challenge,
+                cutoff_time,
+                min_difficulty,
+                risk_time,
+                checkpoint_step,
+                progress_interval,
+                progress_bar: progress_bar.clone(),
+                global_best_difficulty: global_best_difficulty.clone(),
+                risk_window: risk_window.clone(),
+                earliest_min,
+                start_nonce: start_nonces[i],
+                progress: self.nonce_file.as_ref().map(|_| progress[i].clone()),
+                results: results_sender.clone(),
+            });
+        }
+        drop(results_sender);
+
+        // Collect results and return best nonce
+        let workers = self.workers.len() as u64;
+        let mut best_nonce = 0;
+        let mut best_difficulty = 0;
+        let mut best_hash = Hash::default();
+        let mut total_hashes = 0;
+        let mut last_saved_at = Instant::now();
+        loop {
+            let result = tokio::select! {
+                result = results.recv() => result,
+                _ = tokio::time::sleep(Duration::from_millis(100)) => {
+                    if last_saved_at.elapsed().ge(&NONCE_FILE_SAVE_INTERVAL) {
+                        last_saved_at = Instant::now();
+                        self.save_nonce_file(&proof.challenge, resumed_best, &progress);
+                    }
+                    // MI: submit early, the threads keep hashing through the risk time
+                    if let Some(risk_window) = risk_window.as_ref() {
+                        if risk_window.checked_in.load(Ordering::Relaxed).ge(&workers) {
+                            self.save_nonce_file(&proof.challenge, resumed_best, &progress);
+                            return submit_early(&progress_bar, risk_window);
+                        }
+                    }
+                    continue;
+                }
+            };
+            let Some((nonce, difficulty, hash, hashes)) = result else {
+                break;
+            };
+            total_hashes += hashes;
+            if difficulty > best_difficulty {
+                best_difficulty = difficulty;
+                best_nonce = nonce;
+                best_hash = hash;
+            }
+        }
+        self.save_nonce_file(&proof.challenge, resumed_best, &progress);
+        if let Some(best) = resumed_best.filter(|best| best.difficulty.gt(&best_difficulty)) {
+            best_difficulty = best.difficulty;
+            best_nonce = best.nonce;
+            best_hash = Solution::new(best.digest, best.nonce.to_le_bytes()).to_hash();
+        }
+
+        // Update log
+        finish_mining_progress_bar(
+            &progress_bar,
+            format!(
+                "Best hash: {} (difficulty {})",
+                bs58::encode(best_hash.h).into_string(),
+                best_difficulty
+            ),
+        );
+
+        (
+            Solution::new(best_hash.d, best_nonce.to_le_bytes()),
+            total_hashes,
+        )
+    }
+
+    fn save_nonce_file(
+        &self,
+        challenge: &[u8; 32],
+        resumed_best: Option<NonceBest>,
+        progress: &[Arc<Mutex<WorkerProgress>>],
+    ) {
+        let Some(path) = self.nonce_file.as_deref() else {
+            return;
+        };
+        let progress: Vec<WorkerProgress> = progress.iter().map(|p| *p.lock().unwrap()).collect();
+        let best = progress
+            .iter()
+            .filter_map(|p| p.best)
+            .chain(resumed_best)
+            .max_by_key(|best| best.difficulty);
+        NonceFile {
+            challenge: bs58::encode(challenge).into_string(),
+            nonces: progress.iter().map(|p| p.nonce).collect(),
+            best,
+        }
+        .save(path);
+    }
+}
+
+impl HashingJob {
+    fn run(
+        &self,
+        memory: &mut equix::SolverMemory,
+        reporter: bool,
+        all_solutions: bool,
+    ) -> (u64, u32, Hash, u64) {
+        // Start hashing
+        let timer = Instant::now();
+        let mut last_progress_at = timer;
+        let first_nonce = self.start_nonce;
+        let mut nonce = first_nonce;
+        let mut best_nonce = nonce;
+        let mut best_difficulty = 0;
+        let mut best_hash = Hash::default();
+        let mut checked_in = false;
+        loop {
+            // Get hashes
+            let hxs = if all_solutions {
+                drillx::hashes_with_memory(memory, &self.challenge, &nonce.to_le_bytes())
+            } else {
+                drillx::hash_with_memory(memory, &self.challenge, &nonce.to_le_bytes())
+                    .into_iter()
+                    .collect()
+            };
+
+            // Look for best difficulty score in all hashes
+            for hx in hxs {
+                let difficulty = hx.difficulty();
+                if difficulty.gt(&best_difficulty) {
+                    best_nonce = nonce;
+                    best_difficulty = difficulty;
+                    best_hash = hx;
+                    if let Some(risk_window) = self.risk_window.as_ref() {
+                        risk_window.offer(best_difficulty, best_nonce, &best_hash);
+                    }
+                    if best_difficulty.gt(&*self.global_best_difficulty.read().unwrap()) {
+                        *self.global_best_difficulty.write().unwrap() = best_difficulty;
+                    }
+                }
+            }
+
+            // Exit if time has elapsed
+            if nonce % self.checkpoint_step == 0 {
+                if let Some(progress) = self.progress.as_ref() {
+                    *progress.lock().unwrap() = WorkerProgress {
+                        nonce,
+                        best: best_difficulty.gt(&0).then_some(NonceBest {
+                            difficulty: best_difficulty,
+                            nonce: best_nonce,
+                            digest: best_hash.d,
+                        }),
+                    };
+                }
+                let global_best_difficulty = *self.global_best_difficulty.read().unwrap();
+                let current_timestamp = timer.elapsed().as_secs();
+                let report = reporter && last_progress_at.elapsed().ge(&self.progress_interval);
+                if report {
+                    last_progress_at = Instant::now();
+                }
+                // MI: --submit-strategy earliest-min takes the first hash at the min difficulty
+                if self.earliest_min && global_best_difficulty.ge(&self.min_difficulty) {
+                    break;
+                }
+                if current_timestamp.ge(&self.cutoff_time) {
+                    // if min difficulty has been met, or after extra time after deadline (i.e. extra 29 secs)
+                    let settled = global_best_difficulty.ge(&self.min_difficulty)
+                        || current_timestamp.ge(&self.cutoff_time.saturating_add(self.risk_time));
+                    if settled {
+                        // MI: with --pipeline, keep hashing while the submission confirms
+                        match self.risk_window.as_ref().filter(|rw| rw.pipeline) {
+                            Some(risk_window) if !risk_window.is_stopped() => {
+                                if !checked_in {
+                                    checked_in = true;
+                                    risk_window.check_in(nonce - first_nonce);
+                                }
+                            }
+                            _ => break,
+                        }
+                    } else {
+                        // MI: the best hash so far is being submitted
+                        if let Some(risk_window) = self.risk_window.as_ref() {
+                            if risk_window.is_stopped() {
+                                break;
+                            }
+                            if !checked_in && !risk_window.pipeline {
+                                checked_in = true;
+                                risk_window.check_in(nonce - first_nonce);
+                            }
+                        }
+                        if report && !checked_in {
+                            self.progress_bar.set_message(format!(
+                                "Mining... ({} sec surpassed, difficulty {})",
+                                current_timestamp.saturating_sub(self.cutoff_time),
+                                global_best_difficulty,
+                            ));
+                        }
+                    }
+                } else if report {
+                    self.progress_bar.set_message(format!(
+                        "Mining... (difficulty {}, countdown {})",
+                        global_best_difficulty,
+                        format_duration(self.cutoff_time.saturating_sub(current_timestamp) as u32),
+                    ));
+                }
+            }
+
+            // Increment nonce
+            nonce += 1;
+        }
+
+        // Return the best nonce
+        (best_nonce, best_difficulty, best_hash, nonce - first_nonce)
+    }
 }
 
 #[derive(Debug)]
@@ -87,19 +570,37 @@ impl FromStr for SrcType {
 }
 
 impl Miner {
-    pub async fn mine(&self, args: MineArgs) {
-        // Open account, if needed.
-        let signer = self.signer();
-        self.open().await;
+    pub async fn mine(&self, args: MineArgs) -> Result<(), Error> {
+        self.check_fee_payer_separate(args.separate_fee_payer_required)?;
+
+        // Open account, if needed. Only the authority can open its own proof.
+        let signer = self.signer()?;
+        let authority = self.authority()?;
+        if authority.eq(&signer.pubkey()) {
+            self.open().await?;
+        }
 
-        let mut parallel_strategy = ParallelStrategy::Cores(1);
+        let available_core_ids = core_affinity::get_core_ids().unwrap_or_default();
+        let mut parallel_strategy = ParallelStrategy::Cores(first_cores(&available_core_ids, 1));
 
         // Check num threads
         // self.check_num_cores(args.threads);
-        if let Some(cores) = args.cores {
+        let threads_per_core = args.threads_per_core.max(1);
+        if !args.core_mask.is_empty() {
+            // MI: pin to exactly the cores in the mask
+            let core_ids = match select_core_mask(&available_core_ids, &args.core_mask) {
+                Ok(core_ids) => core_ids,
+                Err(err) => return Err(Error::Failed(err)),
+            };
+            println!(
+                "Parallel strategy: cores {:?}, {threads_per_core} threads per core.",
+                args.core_mask
+            );
+            parallel_strategy = ParallelStrategy::Cores(core_ids);
+        } else if let Some(cores) = args.cores {
             self.check_num_cores(cores);
-            parallel_strategy = ParallelStrategy::Cores(cores);
-            println!("Parallel strategy: {cores} cores.");
+            parallel_strategy = ParallelStrategy::Cores(first_cores(&available_core_ids, cores));
+            println!("Parallel strategy: {cores} cores, {threads_per_core} threads per core.");
         } else if let Some(threads) = args.threads {
             self.check_num_threads(threads);
             parallel_strategy = ParallelStrategy::Threads(threads);
@@ -111,411 +612,1089 @@ impl Miner {
             );
         };
 
+        // MI: drop efficiency cores from the selection, if they can be told apart
+        if args.performance_cores_only {
+            if let ParallelStrategy::Cores(core_ids) = &mut parallel_strategy {
+                match performance_cpu_ids() {
+                    Some(performance_cpu_ids) => {
+                        let performance = performance_cores(core_ids, &performance_cpu_ids);
+                        if performance.is_empty() {
+                            println!(
+                                "{} None of the selected cores is a performance core. Using all selected cores.",
+                                "WARNING".bold().yellow(),
+                            );
+                        } else {
+                            println!(
+                                "Performance cores only: {} of {} selected cores.",
+                                performance.len(),
+                                core_ids.len()
+                            );
+                            *core_ids = performance;
+                        }
+                    }
+                    None => println!(
+                        "{} Performance cores cannot be detected on this platform. Using all selected cores.",
+                        "WARNING".bold().yellow(),
+                    ),
+                }
+            }
+        }
+
+        // MI: run the --threads-per-core threads of a core on its SMT siblings, one each
+        if let ParallelStrategy::Cores(core_ids) = &mut parallel_strategy {
+            if threads_per_core.gt(&1) {
+                *core_ids = sibling_cores(core_ids, threads_per_core as usize, thread_siblings)
+                    .map_err(Error::Failed)?;
+                let cpu_ids: Vec<usize> = core_ids.iter().map(|core_id| core_id.id).collect();
+                println!("Hashing threads pinned to cpus {:?}", cpu_ids);
+            }
+        }
+
         let nonce_checkpoint_step: u64 = args.nonce_checkpoint_step;
         let expected_min_difficulty: u32 = args.expected_min_difficulty;
         let extra_fee_difficulty: u32 = args.extra_fee_difficulty;
         let extra_fee_percent: u64 = args.extra_fee_percent;
         let messaging_diff: u32 = args.messaging_diff;
         let risk_time: u64 = args.risk_time;
+        let earliest_min = args.submit_strategy.eq(&SubmitStrategy::EarliestMin);
+        let progress_interval = Duration::from_millis(args.progress_interval);
+
+        // MI: an extra fee tier below the expected min difficulty would apply to every submission
+        if extra_fee_percent.gt(&0) && extra_fee_difficulty.lt(&expected_min_difficulty) {
+            return Err(Error::Failed(format!(
+                "--extra-fee-difficulty ({}) must be at least --expected-min-difficulty ({})",
+                extra_fee_difficulty, expected_min_difficulty
+            )));
+        }
+
+        // Check compute budget
+        if args
+            .compute_budget
+            .saturating_add(args.reset_extra_cu)
+            .gt(&CU_LIMIT_MAX)
+        {
+            println!(
+                "{} Compute budget with reset ({} CUs) exceeds the transaction maximum ({} CUs)",
+                "WARNING".bold().yellow(),
+                args.compute_budget.saturating_add(args.reset_extra_cu),
+                CU_LIMIT_MAX
+            );
+        }
+
+        // MI: hashes below the program's min difficulty are rejected on chain
+        let mut program_min_difficulty = get_config(&self.rpc_client).await.min_difficulty;
+        println!(
+            "Hashing: drillx {} (equix + keccak), nonce checkpoint every {} hashes.",
+            env!("ORE_CLI_DRILLX_VERSION"),
+            nonce_checkpoint_step
+        );
+        println!("Program min difficulty: {}", program_min_difficulty);
+        check_min_difficulty(expected_min_difficulty, program_min_difficulty);
+
+        // MI: validate the jito tip account, the tip is skipped when the amount is zero
+        let jito_tip_account = match args.jito_tip_account.as_deref().map(Pubkey::from_str) {
+            None => None,
+            Some(Ok(tip_account)) => {
+                if !JITO_TIP_ACCOUNTS.contains(&tip_account.to_string().as_str()) {
+                    println!(
+                        "{} {} is not a known Jito tip account",
+                        "WARNING".bold().yellow(),
+                        tip_account
+                    );
+                }
+                Some(tip_account)
+            }
+            Some(Err(err)) => {
+                return Err(Error::Failed(format!("Invalid jito tip account: {}", err)));
+            }
+        };
+        if args.jito_tip.gt(&0) {
+            println!("Jito tip: {} SOL", lamports_to_sol(args.jito_tip));
+        }
 
         // MI
+        let webhook_min_interval = Duration::from_secs(self.webhook_min_interval);
         let (slack_message_sender, slack_message_receiver) =
             mpsc::unbounded_channel::<RewardsMessage>();
         let (discord_message_sender, discord_message_receiver) =
             mpsc::unbounded_channel::<RewardsMessage>();
+        let mut messaging_tasks = vec![];
         if let Some(slack_webhook) = self.slack_webhook.clone() {
             // Handle slack messages to send
-            tokio::spawn(async move {
-                slack_messaging_system(slack_webhook, slack_message_receiver).await;
-            });
+            messaging_tasks.push(tokio::spawn(async move {
+                slack_messaging_system(slack_webhook, slack_message_receiver, webhook_min_interval)
+                    .await;
+            }));
         }
         if let Some(discord_webhook) = self.discord_webhook.clone() {
             // Handle discord messages to send
-            tokio::spawn(async move {
-                discord_messaging_system(discord_webhook, discord_message_receiver).await;
-            });
+            messaging_tasks.push(tokio::spawn(async move {
+                discord_messaging_system(
+                    discord_webhook,
+                    discord_message_receiver,
+                    webhook_min_interval,
+                )
+                .await;
+            }));
         }
 
+        // MI: heartbeat summaries, fed by a separate timer task
+        let mut heartbeat_task = None;
+        let heartbeat_stats = args
+            .heartbeat
+            .filter(|_| self.slack_webhook.is_some() || self.discord_webhook.is_some())
+            .map(|minutes| {
+                let stats = Arc::new(Mutex::new(HeartbeatStats::default()));
+                let slack_sender = self
+                    .slack_webhook
+                    .as_ref()
+                    .map(|_| slack_message_sender.clone());
+                let discord_sender = self
+                    .discord_webhook
+                    .as_ref()
+                    .map(|_| discord_message_sender.clone());
+                heartbeat_task = Some(tokio::spawn(heartbeat_system(
+                    Duration::from_secs(minutes.saturating_mul(60)),
+                    stats.clone(),
+                    slack_sender,
+                    discord_sender,
+                )));
+                stats
+            });
+
+        // MI: aggregated stats line, printed by a separate timer task
+        let output_stats = args.output_interval.map(|seconds| {
+            let stats = Arc::new(Mutex::new(OutputStats::default()));
+            tokio::spawn(output_system(
+                Duration::from_secs(seconds.max(1)),
+                stats.clone(),
+            ));
+            stats
+        });
+        let quiet = args.quiet || output_stats.is_some();
+
+        // MI: profit guard
+        let mut profit_guard = args
+            .profit_guard
+            .zip(args.ore_price)
+            .map(|(window, ore_price)| ProfitGuard::new(window, ore_price));
+
+        // MI: fee payer burn rate, measured from the first round
+        let mut fee_payer_start_balance = None;
+        let mut rounds_submitted = 0u64;
+        let mut fee_payer_low = false;
+
+        // MI: consecutive submitted rounds without rewards
+        let mut idle_streak = 0u64;
+
+        // MI: a cluster clock far from local time shifts every cutoff
+        let mut last_clock_skew = self.check_clock_skew(args.max_clock_skew, None).await;
+        let mut rounds_since_clock_check = 0u64;
+
+        // MI: structured events for dashboards
+        let events = match args.event_socket.as_deref() {
+            Some(addr) => match EventStream::listen(addr).await {
+                Ok(events) => {
+                    println!("Streaming events on {}", addr);
+                    events
+                }
+                Err(err) => {
+                    return Err(Error::Failed(format!(
+                        "Failed to open event socket {}: {}",
+                        addr, err
+                    )));
+                }
+            },
+            None => EventStream::disabled(),
+        };
+
+        // MI: finish the in-flight round once a shutdown is requested, e.g. on SIGINT/SIGTERM
+        let shutdown = self.shutdown.clone();
+        let session_started_at = Instant::now();
+        let mut session_start_balance = None;
+
         // Start mining loop
+        let hashing_pool = HashingPool::new(
+            &parallel_strategy,
+            args.thread_stack_size,
+            args.nonce_file.clone(),
+            args.nice,
+        );
         let mut last_hash_at = 0;
         let mut last_balance = 0;
         let mut last_difficulty = 0;
+        let mut last_signature = None;
+        let mut pipelined_solution: Option<([u8; 32], u32, Solution)> = None;
+        let mut low_rounds: u64 = 0;
+        let mut stale_skips: u64 = 0;
+        let mut fee_cap_hits: u64 = 0;
+        let mut failed_rounds: u64 = 0;
+        // MI: ORE held in the wallet when mining started, compounding stakes only what arrives on top
+        let mut compound_baseline = match args.compound {
+            Some(_) => self.wallet_ore_balance().await,
+            None => None,
+        };
+        let mut compound_due = false;
+        let mut last_hashrate: f64 = 0.0;
+        let reporter = args.report_url.clone().map(|url| {
+            let secret = args
+                .report_secret
+                .clone()
+                .or_else(|| std::env::var("ORE_REPORT_SECRET").ok())
+                .filter(|secret| !secret.is_empty());
+            Reporter::spawn(url, secret)
+        });
         loop {
             // Fetch proof
+            let mut timings = RoundTimings::default();
+            let fetch_started_at = Instant::now();
             let config = get_config(&self.rpc_client).await;
-            let proof =
-                get_updated_proof_with_authority(&self.rpc_client, signer.pubkey(), last_hash_at)
-                    .await;
+            timings.config_fetch = fetch_started_at.elapsed();
+            if config.min_difficulty.ne(&program_min_difficulty) {
+                println!(
+                    "{} Program min difficulty changed: {} -> {}",
+                    "WARNING".bold().yellow(),
+                    program_min_difficulty,
+                    config.min_difficulty
+                );
+                program_min_difficulty = config.min_difficulty;
+                check_min_difficulty(expected_min_difficulty, program_min_difficulty);
+            }
+            let fetch_started_at = Instant::now();
+            let proof = match get_updated_proof_with_authority(
+                &self.rpc_client,
+                authority,
+                last_hash_at,
+                Duration::from_millis(args.challenge_refresh_interval.max(1)),
+                PROOF_UPDATE_TIMEOUT,
+            )
+            .await
+            {
+                Some(proof) => proof,
+                // MI: the proof did not advance, check whether the last transaction landed
+                None => {
+                    let landed = match last_signature {
+                        Some(sig) => signature_landed(&self.rpc_client, sig).await,
+                        None => false,
+                    };
+                    if landed {
+                        println!(
+                            "{} Last transaction landed but the proof has not updated yet. Proceeding with the latest proof...",
+                            "WARNING".bold().yellow(),
+                        );
+                    } else {
+                        println!(
+                            "{} Last transaction did not land. Mining a new solution to resubmit...",
+                            "WARNING".bold().yellow(),
+                        );
+                        let text = "Last transaction did not land, the proof did not update. Mining continues.";
+                        let _ = slack_message_sender.send(RewardsMessage::Error(text.into()));
+                        let _ = discord_message_sender.send(RewardsMessage::Error(text.into()));
+                        last_signature = None;
+                        last_difficulty = 0;
+
+                        // MI: a fresh, likely higher, fee estimate for the next round
+                        self.invalidate_dynamic_fee_cache();
+
+                        // MI: back off from a congested network instead of paying for more failures
+                        failed_rounds += 1;
+                        if let Some(cool_down) = args.cool_down {
+                            if failed_rounds.ge(&args.cool_down_after.max(1)) {
+                                let doublings =
+                                    failed_rounds.saturating_sub(args.cool_down_after.max(1));
+                                let secs = cool_down
+                                    .saturating_mul(1 << doublings.min(16))
+                                    .min(args.cool_down_max);
+                                let text = format!(
+                                    "Cool-down: {} rounds in a row did not land. Sleeping {} sec before the next attempt...",
+                                    failed_rounds, secs
+                                );
+                                println!("{} {}", "WARNING".bold().yellow(), text);
+                                if self.slack_webhook.is_some() {
+                                    let _ = slack_message_sender
+                                        .send(RewardsMessage::Alert(text.clone()));
+                                }
+                                if self.discord_webhook.is_some() {
+                                    let _ =
+                                        discord_message_sender.send(RewardsMessage::Alert(text));
+                                }
+                                // A shutdown ends the wait, the exit check below stops the loop
+                                sleep_unless_shutdown(Duration::from_secs(secs), &shutdown).await;
+                            }
+                        }
+                    }
+                    get_proof_with_authority(&self.rpc_client, authority).await
+                }
+            };
+            timings.proof_fetch = fetch_started_at.elapsed();
 
             let curr_balance_string = amount_u64_to_string(proof.balance);
-            let delta_change_string =
-                amount_u64_to_string(proof.balance.saturating_sub(last_balance));
+            let delta_change = proof.balance.saturating_sub(last_balance);
+            let delta_change_string = amount_u64_to_string(delta_change);
+            let last_sig = last_signature.take();
+            if last_sig.is_some() && failed_rounds.gt(&0) {
+                if args.cool_down.is_some() && failed_rounds.ge(&args.cool_down_after.max(1)) {
+                    println!("Cool-down over, the last transaction landed.");
+                }
+                failed_rounds = 0;
+            }
+            if let Some(stats) = heartbeat_stats.as_ref() {
+                let mut stats = stats.lock().unwrap();
+                if last_hash_at.gt(&0) {
+                    stats.rewards += proof.balance.saturating_sub(last_balance);
+                }
+                stats.balance = proof.balance;
+            }
+            if let Some(stats) = output_stats.as_ref() {
+                if last_hash_at.gt(&0) {
+                    stats.lock().unwrap().rewards += proof.balance.saturating_sub(last_balance);
+                }
+            }
+            let tx_link = last_sig
+                .map(|sig| self.explorer_link(&sig))
+                .unwrap_or_default();
             // notify slack channel if necessary
             if last_difficulty >= messaging_diff {
                 if self.slack_webhook.is_some() {
                     let _ = slack_message_sender.send(RewardsMessage::Rewards(
                         last_difficulty,
-                        f64::from_str(&delta_change_string).unwrap(),
-                        f64::from_str(&curr_balance_string).unwrap(),
+                        delta_change,
+                        proof.balance,
+                        tx_link.clone(),
                     ));
                 }
                 if self.discord_webhook.is_some() {
                     let _ = discord_message_sender.send(RewardsMessage::Rewards(
                         last_difficulty,
-                        f64::from_str(&delta_change_string).unwrap(),
-                        f64::from_str(&curr_balance_string).unwrap(),
+                        delta_change,
+                        proof.balance,
+                        tx_link.clone(),
                     ));
                 }
             }
 
-            println!(
-                "\n\nStake: {} ORE\n{}  Multiplier: {:12}x",
-                // amount_u64_to_string(proof.balance),
-                curr_balance_string,
-                if last_hash_at.gt(&0) {
+            // pop a desktop notification, if enabled
+            if args.desktop_notify && last_sig.is_some() && last_difficulty >= messaging_diff {
+                utils::desktop_notify(
+                    "ORE solution landed".to_string(),
                     format!(
-                        "  Change: {} ORE\n",
-                        // amount_u64_to_string(proof.balance.saturating_sub(last_balance))
-                        delta_change_string
-                    )
-                } else {
-                    "".to_string()
-                },
-                calculate_multiplier(proof.balance, config.top_balance)
-            );
+                        "Difficulty: {}\nReward: {} ORE",
+                        last_difficulty, delta_change_string
+                    ),
+                );
+            }
+
+            events.emit(MinerEvent::Balance {
+                balance: amount_u64_to_f64(proof.balance),
+                change: amount_u64_to_f64(delta_change),
+            });
+
+            // run solution callback, if configured
+            if let (Some(on_solution), Some(sig)) = (self.on_solution.clone(), last_sig) {
+                utils::run_hook(
+                    on_solution,
+                    vec![
+                        ("ORE_DIFFICULTY", last_difficulty.to_string()),
+                        ("ORE_REWARD", delta_change_string.clone()),
+                        ("ORE_SIGNATURE", sig.to_string()),
+                    ],
+                );
+            }
+
+            // MI: report the landed round to --report-url
+            if let (Some(reporter), Some(sig)) = (reporter.as_ref(), last_sig) {
+                reporter.report(RoundReport {
+                    wallet: authority.to_string(),
+                    difficulty: last_difficulty,
+                    reward: amount_u64_to_f64(delta_change),
+                    fee_lamports: *self.last_fee_lamports.read().unwrap(),
+                    signature: sig.to_string(),
+                    hashrate: last_hashrate,
+                    timestamp: Local::now().timestamp(),
+                });
+            }
+
+            // MI: keep low rounds to one line when a log threshold is set
+            let low_round = last_hash_at.gt(&0)
+                && args
+                    .log_difficulty_threshold
+                    .is_some_and(|threshold| last_difficulty.lt(&threshold));
+            if output_stats.is_some() {
+                // MI: the --output-interval stats line replaces the per-round output
+            } else if low_round {
+                low_rounds += 1;
+                println!(
+                    "Difficulty {}  Change: {} ORE  Stake: {} ORE  ({} low rounds)",
+                    last_difficulty, delta_change_string, curr_balance_string, low_rounds
+                );
+            } else {
+                println!(
+                    "\n\nStake: {} ORE\n{}  Multiplier: {:12}x{}",
+                    // amount_u64_to_string(proof.balance),
+                    curr_balance_string,
+                    if last_hash_at.gt(&0) {
+                        format!(
+                            "  Change: {} ORE\n",
+                            // amount_u64_to_string(proof.balance.saturating_sub(last_balance))
+                            delta_change_string
+                        )
+                    } else {
+                        "".to_string()
+                    },
+                    calculate_multiplier(proof.balance, config.top_balance),
+                    if low_rounds.gt(&0) {
+                        format!("\n  Low rounds so far: {}", low_rounds)
+                    } else {
+                        "".to_string()
+                    }
+                );
+            }
+
+            // Check profitability of the last rounds
+            if let Some(guard) = profit_guard.as_mut() {
+                if last_hash_at.gt(&0) {
+                    guard.record(
+                        *self.last_fee_lamports.read().unwrap(),
+                        proof.balance.saturating_sub(last_balance),
+                    );
+                    println!("  Profitability: {:.4}x", guard.ratio());
+                }
+                if guard.is_unprofitable() {
+                    println!(
+                        "{} SOL spent on fees has outpaced ORE earned for {} rounds",
+                        "WARNING".bold().yellow(),
+                        guard.window
+                    );
+                    match args.profit_guard_pause {
+                        Some(pause) => {
+                            println!("Pausing for {} sec...", pause);
+                            guard.rounds.clear();
+                            tokio::time::sleep(Duration::from_secs(pause)).await;
+                        }
+                        None => return stopped(&args),
+                    }
+                }
+            }
+
+            // Check the fee payer can keep paying for submissions
+            if let Some(min_balance) = args.fee_payer_min_balance {
+                if let Ok(balance) = self
+                    .rpc_client
+                    .get_balance(&self.fee_payer()?.pubkey())
+                    .await
+                {
+                    let start_balance = *fee_payer_start_balance.get_or_insert(balance);
+                    if balance < sol_to_lamports(min_balance) {
+                        let rounds_left = match start_balance
+                            .saturating_sub(balance)
+                            .checked_div(rounds_submitted)
+                        {
+                            Some(burn) if burn.gt(&0) => format!(
+                                "~{} rounds left at the current burn rate",
+                                balance
+                                    .saturating_sub(sol_to_lamports(MIN_SOL_BALANCE))
+                                    .saturating_div(burn)
+                            ),
+                            _ => "burn rate unknown".to_string(),
+                        };
+                        let text = format!(
+                            "Fee payer balance is low: {} SOL ({})",
+                            lamports_to_sol(balance),
+                            rounds_left
+                        );
+                        println!("{} {}", "WARNING".bold().yellow(), text);
+                        if !fee_payer_low {
+                            if self.slack_webhook.is_some() {
+                                let _ =
+                                    slack_message_sender.send(RewardsMessage::Alert(text.clone()));
+                            }
+                            if self.discord_webhook.is_some() {
+                                let _ = discord_message_sender.send(RewardsMessage::Alert(text));
+                            }
+                        }
+                        fee_payer_low = true;
+                        if let Some(pause) = args.fee_payer_low_pause {
+                            println!("Pausing for {} sec...", pause);
+                            tokio::time::sleep(Duration::from_secs(pause)).await;
+                        }
+                    } else {
+                        fee_payer_low = false;
+                    }
+                }
+            }
+
+            // Back off while rounds earn nothing, until a bus has rewards again
+            if let Some((idle_rounds, idle_backoff)) = args.idle_rounds.zip(args.idle_backoff) {
+                if last_sig.is_some() {
+                    if proof.balance.gt(&last_balance) {
+                        idle_streak = 0;
+                    } else {
+                        idle_streak += 1;
+                    }
+                }
+                if idle_streak.ge(&idle_rounds) {
+                    let text = format!(
+                        "Idle: {} rounds without rewards. Backing off until a bus has rewards...",
+                        idle_streak
+                    );
+                    println!("{} {}", "WARNING".bold().yellow(), text);
+                    if self.slack_webhook.is_some() {
+                        let _ = slack_message_sender.send(RewardsMessage::Alert(text.clone()));
+                    }
+                    if self.discord_webhook.is_some() {
+                        let _ = discord_message_sender.send(RewardsMessage::Alert(text));
+                    }
+                    let mut interrupted = false;
+                    loop {
+                        println!("Sleeping {} sec...", idle_backoff);
+                        if sleep_unless_shutdown(Duration::from_secs(idle_backoff), &shutdown).await
+                        {
+                            interrupted = true;
+                            break;
+                        }
+                        let (bus, bus_rewards) = self.find_bus().await;
+                        if bus_rewards.gt(&0) {
+                            println!(
+                                "Bus {} has {} ORE. Resuming at full rate.",
+                                bus,
+                                amount_u64_to_string(bus_rewards)
+                            );
+                            break;
+                        }
+                    }
+                    // Start over from the latest proof, as if this was the first round. A shutdown
+                    // during the backoff goes straight to the exit below.
+                    if !interrupted {
+                        idle_streak = 0;
+                        last_hash_at = 0;
+                        last_difficulty = 0;
+                        continue;
+                    }
+                }
+            }
+
             last_hash_at = proof.last_hash_at;
             last_balance = proof.balance;
 
+            // Exit cleanly between rounds once a shutdown was requested
+            let start_balance = *session_start_balance.get_or_insert(proof.balance);
+            let time_is_up = args
+                .max_duration
+                .is_some_and(|max_duration| session_started_at.elapsed().ge(&max_duration));
+            if time_is_up {
+                println!("Max session duration reached.");
+            }
+            if time_is_up || shutdown.load(Ordering::Relaxed) {
+                println!("{}", "Shutting down".bold());
+                println!(
+                    "  Session: {} rounds submitted in {}",
+                    rounds_submitted,
+                    format_duration(session_started_at.elapsed().as_secs() as u32)
+                );
+                println!(
+                    "  Rewards: {} ORE",
+                    amount_u64_to_string(proof.balance.saturating_sub(start_balance))
+                );
+                if low_rounds.gt(&0) {
+                    println!("  Low rounds: {}", low_rounds);
+                }
+                if fee_cap_hits.gt(&0) {
+                    println!(
+                        "  Fee cap hits: {} (the priority fee cap clamped the fee estimate)",
+                        fee_cap_hits
+                    );
+                }
+                return stopped(&args);
+            }
+
+            // Re-check the clock skew every few rounds
+            rounds_since_clock_check += 1;
+            if rounds_since_clock_check.ge(&CLOCK_SKEW_CHECK_ROUNDS) {
+                rounds_since_clock_check = 0;
+                last_clock_skew = self
+                    .check_clock_skew(args.max_clock_skew, Some(last_clock_skew))
+                    .await;
+            }
+
             // Calculate cutoff time
-            let cutoff_time = self.get_cutoff(proof, args.buffer_time).await;
+            let cutoff_time = self
+                .get_cutoff(proof, args.buffer_time, args.slot_timing)
+                .await;
+            events.emit(MinerEvent::RoundStart {
+                challenge: bs58::encode(proof.challenge).into_string(),
+                cutoff_time,
+            });
 
             // Run drillx
-            let solution = match parallel_strategy {
-                ParallelStrategy::Cores(cores) => {
-                    Self::find_hash_par_cores(
-                        proof,
-                        cutoff_time,
-                        cores,
-                        // config.min_difficulty as u32,
-                        expected_min_difficulty,
-                        risk_time,
-                        nonce_checkpoint_step,
-                    )
-                    .await
+            let risk_window =
+                (args.submit_only_if_improves.is_some() || args.pipeline).then(|| {
+                    Arc::new(RiskWindowHashing {
+                        pipeline: args.pipeline,
+                        ..Default::default()
+                    })
+                });
+            let hashing_started_at = Instant::now();
+            // MI: the last submission did not land, resubmit what was hashed while it confirmed
+            let pipelined = pipelined_solution
+                .take()
+                .filter(|(challenge, ..)| challenge.eq(&proof.challenge));
+            let (mut solution, hashes) = match pipelined {
+                Some((_, difficulty, solution)) => {
+                    println!(
+                        "Resubmitting pipelined solution (difficulty {})",
+                        difficulty
+                    );
+                    (solution, 0)
                 }
-                ParallelStrategy::Threads(threads) => {
-                    Self::find_hash_par_threads(
+                None => {
+                    let hashing = hashing_pool.find_hash(
                         proof,
                         cutoff_time,
-                        threads,
                         // config.min_difficulty as u32,
                         expected_min_difficulty,
                         risk_time,
                         nonce_checkpoint_step,
-                    )
-                    .await
+                        progress_interval,
+                        quiet,
+                        risk_window.clone(),
+                        earliest_min,
+                    );
+                    // MI: compound while the pool hashes, so staking does not hold up the round
+                    match args.compound.filter(|_| compound_due) {
+                        Some(threshold) => {
+                            compound_due = false;
+                            let (found, compounded) = tokio::join!(
+                                hashing,
+                                self.compound(
+                                    threshold,
+                                    args.target_multiplier,
+                                    &mut compound_baseline
+                                )
+                            );
+                            match compounded {
+                                // MI: the staked ORE lands in the proof balance, keep it out
+                                // of the next reward delta and the session rewards
+                                Ok(staked) => {
+                                    last_balance = last_balance.saturating_add(staked);
+                                    if let Some(start_balance) = session_start_balance.as_mut() {
+                                        *start_balance = start_balance.saturating_add(staked);
+                                    }
+                                }
+                                Err(err) => {
+                                    println!(
+                                        "{} Compounding failed: {}",
+                                        "WARNING".bold().yellow(),
+                                        err
+                                    );
+                                }
+                            }
+                            found
+                        }
+                        None => hashing.await,
+                    }
                 }
             };
 
-            let solution_difficulty = solution.to_hash().difficulty();
-            let difficulty_payload = DifficultyPayload {
-                solution_difficulty,
-                expected_min_difficulty,
-                extra_fee_difficulty,
-                extra_fee_percent,
-                messaging_diff,
-            };
-
-            // Build instruction set
-            let mut ixs = vec![ore_api::instruction::auth(proof_pubkey(signer.pubkey()))];
-            let mut compute_budget = 500_000;
-            // if self.should_reset(config).await {
-            if self.should_reset(config).await && rand::thread_rng().gen_range(0..100).eq(&0) {
-                compute_budget += 100_000;
-                ixs.push(ore_api::instruction::reset(signer.pubkey()));
+            // MI: the program rejects a hash submitted before the cutoff, hold it until then
+            if earliest_min {
+                let submit_at = hashing_started_at + Duration::from_secs(cutoff_time);
+                if let Some(wait) = submit_at.checked_duration_since(Instant::now()) {
+                    if !quiet {
+                        println!(
+                            "Holding the solution {} sec until the program accepts it...",
+                            wait.as_secs()
+                        );
+                    }
+                    tokio::time::sleep(wait).await;
+                }
             }
 
-            // Build mine ix
-            ixs.push(ore_api::instruction::mine(
-                signer.pubkey(),
-                signer.pubkey(),
-                self.find_bus().await,
-                solution,
-            ));
-
-            // Submit transaction
-            // MI
-            // self.send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
-            //     .await
-            //     .ok();
-            if self
-                .send_and_confirm(
-                    &ixs,
-                    ComputeBudget::Fixed(compute_budget),
-                    false,
-                    // Some(solution.to_hash().difficulty()),
-                    Some(difficulty_payload),
-                )
-                .await
-                .is_ok()
-            {
-                if !self.no_sound_notification {
-                    utils::play_sound();
+            let mut solution_difficulty = solution.to_hash().difficulty();
+            events.emit(MinerEvent::SolutionFound {
+                difficulty: solution_difficulty,
+                hashes,
+                nonce: u64::from_le_bytes(solution.n),
+            });
+            if let Some(previous) = Records::update(signer.pubkey(), &solution) {
+                if previous.gt(&0) {
+                    println!(
+                        "{} New personal best! Difficulty {} beats your record of {}",
+                        "RECORD".bold().magenta(),
+                        solution_difficulty,
+                        previous
+                    );
                 }
-
-                last_difficulty = solution_difficulty;
-            } else {
-                // MI: when some error like 0x0 (need reset) occurs, we need to exit loop to avoid hang-up
-                return;
             }
-        }
-    }
-
-    // MI: since 2.0
-    async fn find_hash_par_cores(
-        proof: Proof,
-        cutoff_time: u64,
-        cores: u64,
-        min_difficulty: u32,
-        risk_time: u64,
-        checkpoint_step: u64,
-    ) -> Solution {
-        // Dispatch job to each thread
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        let global_best_difficulty = Arc::new(RwLock::new(0u32));
-        progress_bar.set_message("Mining...");
-        let core_ids = core_affinity::get_core_ids().unwrap();
-        let handles: Vec<_> = core_ids
-            .into_iter()
-            .map(|i| {
-                let global_best_difficulty = Arc::clone(&global_best_difficulty);
-                std::thread::spawn({
-                    let proof = proof.clone();
-                    let progress_bar = progress_bar.clone();
-                    let mut memory = equix::SolverMemory::new();
-                    move || {
-                        // Return if core should not be used
-                        if (i.id as u64).ge(&cores) {
-                            return (0, 0, Hash::default());
-                        }
+            timings.hashing = hashing_started_at.elapsed();
+            if hashes.gt(&0) {
+                last_hashrate = hashes as f64 / timings.hashing.as_secs_f64().max(1.0);
+            }
+            if let Some(stats) = heartbeat_stats.as_ref() {
+                let mut stats = stats.lock().unwrap();
+                stats.hashes += hashes;
+                stats.hashing_time += hashing_started_at.elapsed();
+            }
+            if let Some(stats) = output_stats.as_ref() {
+                let mut stats = stats.lock().unwrap();
+                stats.hashes += hashes;
+                stats.hashing_time += hashing_started_at.elapsed();
+            }
 
-                        // Pin to core
-                        let _ = core_affinity::set_for_current(i);
+            // Build instruction set
+            // if self.should_reset(config).await {
+            // MI: only a fraction of miners bundle the reset to avoid racing for it
+            let mut reset = self.should_reset(config).await
+                && rand::thread_rng()
+                    .gen_range(0..100)
+                    .lt(&args.reset_probability);
+            let mut config = config;
+            let mut reset_attempts = 0;
+            let mut replaced_difficulty = None;
+            loop {
+                let mut compute_budget = args.compute_budget;
+                if reset {
+                    compute_budget = compute_budget.saturating_add(args.reset_extra_cu);
+                }
+                let (bus, bus_rewards) = self.find_bus().await;
 
-                        // Start hashing
-                        let timer = Instant::now();
-                        let mut nonce = u64::MAX.saturating_div(cores).saturating_mul(i.id as u64);
-                        let mut best_nonce = nonce;
-                        let mut best_difficulty = 0;
-                        let mut best_hash = Hash::default();
+                // MI: a drained bus cannot pay out, wait for the reset to refill the buses.
+                // A bundled reset refills them in the same transaction.
+                if args.ignore_empty_buses && !reset {
+                    let threshold = args.min_bus_rewards.map_or_else(
+                        || {
+                            estimate_reward(
+                                &config,
+                                calculate_multiplier(proof.balance, config.top_balance),
+                                solution_difficulty,
+                            )
+                        },
+                        amount_f64_to_u64,
+                    );
+                    if bus_rewards.lt(&threshold) {
+                        println!(
+                            "{} Top bus has {} ORE, below the {} ORE threshold. Waiting for the next epoch reset before submitting...",
+                            "WAIT".bold().yellow(),
+                            amount_u64_to_string(bus_rewards),
+                            amount_u64_to_string(threshold)
+                        );
+                        let last_reset_at = config.last_reset_at;
+                        let mut shutting_down = false;
                         loop {
-                            // Get hashes
-                            let hxs = drillx::hashes_with_memory(
-                                &mut memory,
-                                &proof.challenge,
-                                &nonce.to_le_bytes(),
-                            );
-
-                            // Look for best difficulty score in all hashes
-                            for hx in hxs {
-                                let difficulty = hx.difficulty();
-                                if difficulty.gt(&best_difficulty) {
-                                    best_nonce = nonce;
-                                    best_difficulty = difficulty;
-                                    best_hash = hx;
-                                    if best_difficulty.gt(&*global_best_difficulty.read().unwrap())
-                                    {
-                                        *global_best_difficulty.write().unwrap() = best_difficulty;
-                                    }
-                                }
+                            if shutdown.load(Ordering::Relaxed) {
+                                shutting_down = true;
+                                break;
+                            }
+                            tokio::time::sleep(BUS_RESET_POLL_INTERVAL).await;
+                            config = get_config(&self.rpc_client).await;
+                            if config.last_reset_at.ne(&last_reset_at) {
+                                println!("Epoch reset. Selecting a bus...");
+                                break;
+                            }
+                            // MI: nobody reset the expired epoch, bundle the reset ourselves
+                            if self.should_reset(config).await {
+                                println!("Epoch ended without a reset. Bundling the reset...");
+                                reset = true;
+                                break;
                             }
+                        }
+                        if shutting_down {
+                            println!("Shutdown requested. Skipping the round instead of waiting for the reset.");
+                            break;
+                        }
+                        continue;
+                    }
+                }
 
-                            // Exit if time has elapsed
-                            if nonce % checkpoint_step == 0 {
-                                let global_best_difficulty =
-                                    *global_best_difficulty.read().unwrap();
-                                let current_timestamp = timer.elapsed().as_secs();
-                                if current_timestamp.ge(&cutoff_time) {
-                                    if global_best_difficulty.ge(&min_difficulty) {
-                                        // if min difficulty has been met
-                                        break;
-                                    } else {
-                                        // hashes for extra time after deadline (i.e. extra 29 secs)
-                                        if current_timestamp
-                                            .ge(&cutoff_time.saturating_add(risk_time))
-                                        {
-                                            break;
-                                        }
-                                        if i.id == 0 {
-                                            progress_bar.set_message(format!(
-                                                "Mining... ({} sec surpassed, difficulty {})",
-                                                current_timestamp.saturating_sub(cutoff_time),
-                                                global_best_difficulty,
-                                            ));
-                                        }
-                                    }
-                                } else if i.id == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... (difficulty {}, countdown {})",
-                                        global_best_difficulty,
-                                        format_duration(
-                                            cutoff_time.saturating_sub(current_timestamp) as u32
-                                        ),
-                                    ));
-                                }
-                            }
+                let mut ixs = build_mine_ixs(signer.pubkey(), authority, bus, solution, reset);
+                if args.jito_tip.gt(&0) {
+                    let tip_account = jito_tip_account.unwrap_or_else(|| {
+                        let i = rand::thread_rng().gen_range(0..JITO_TIP_ACCOUNTS.len());
+                        Pubkey::from_str(JITO_TIP_ACCOUNTS[i]).unwrap()
+                    });
+                    ixs.push(transfer(
+                        &self.fee_payer()?.pubkey(),
+                        &tip_account,
+                        args.jito_tip,
+                    ));
+                }
 
-                            // Increment nonce
-                            nonce += 1;
-                        }
+                // MI: cap the priority fee at a fraction of the estimated reward value
+                let max_priority_fee = args.max_fee_fraction.zip(args.ore_price).map(
+                    |(max_fee_fraction, ore_price)| {
+                        let reward = estimate_reward(
+                            &config,
+                            calculate_multiplier(proof.balance, config.top_balance),
+                            solution_difficulty,
+                        );
+                        let max_fee = sol_to_lamports(amount_u64_to_f64(reward) * ore_price) as f64
+                            * max_fee_fraction.clamp(0.0, 1.0);
+                        (max_fee * 1_000_000f64 / compute_budget as f64) as u64
+                    },
+                );
+                let difficulty_payload = DifficultyPayload {
+                    solution_difficulty,
+                    expected_min_difficulty,
+                    extra_fee_difficulty,
+                    extra_fee_percent,
+                    messaging_diff,
+                    max_priority_fee,
+                    replace_if_improves: risk_window.clone().zip(args.submit_only_if_improves),
+                };
 
-                        // Return the best nonce
-                        (best_nonce, best_difficulty, best_hash)
+                // MI: a solution for an old challenge is sure to fail, don't pay fees for it
+                if !args.no_challenge_check {
+                    let latest_proof = get_proof_with_authority(&self.rpc_client, authority).await;
+                    if latest_proof.challenge.ne(&proof.challenge) {
+                        stale_skips += 1;
+                        println!(
+                            "{} Challenge changed before submitting, the proof was updated elsewhere. Skipping the stale solution ({} skipped this session)...",
+                            "SKIP".bold().yellow(),
+                            stale_skips
+                        );
+                        break;
                     }
-                })
-            })
-            .collect();
+                }
 
-        // Join handles and return best nonce
-        let mut best_nonce = 0;
-        let mut best_difficulty = 0;
-        let mut best_hash = Hash::default();
-        for h in handles {
-            if let Ok((nonce, difficulty, hash)) = h.join() {
-                if difficulty > best_difficulty {
-                    best_difficulty = difficulty;
-                    best_nonce = nonce;
-                    best_hash = hash;
+                // Submit transaction
+                // MI
+                // self.send_and_confirm(&ixs, ComputeBudget::Fixed(compute_budget), false)
+                //     .await
+                //     .ok();
+                let send_started_at = Instant::now();
+                let result = self
+                    .send_and_confirm(
+                        &ixs,
+                        ComputeBudget::Fixed(compute_budget),
+                        false,
+                        // Some(solution.to_hash().difficulty()),
+                        Some(difficulty_payload),
+                    )
+                    .await;
+                timings.record_send(
+                    send_started_at.elapsed(),
+                    *self.last_send_timings.read().unwrap(),
+                );
+                // MI: a binding cap lowers the chance to land during congestion
+                if *self.last_fee_capped.read().unwrap() {
+                    fee_cap_hits += 1;
+                    if args.warn_on_fee_cap_hit {
+                        println!(
+                            "{} Priority fee capped at {} microlamports ({} cap hits this session). Consider raising --priority-fee-cap.",
+                            "WARNING".bold().yellow(),
+                            self.priority_fee_cap.unwrap_or(0),
+                            fee_cap_hits
+                        );
+                    }
                 }
-            }
-        }
+                match result {
+                    Ok(sig) => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: true,
+                            signature: Some(sig.to_string()),
+                            error: None,
+                        });
+                        last_signature = Some(sig);
+                        rounds_submitted += 1;
+                        if let Some(stats) = heartbeat_stats.as_ref() {
+                            stats.lock().unwrap().rounds += 1;
+                        }
+                        if let Some(stats) = output_stats.as_ref() {
+                            stats.lock().unwrap().record_round(
+                                solution_difficulty,
+                                *self.last_fee_lamports.read().unwrap(),
+                            );
+                        } else {
+                            println!("  Transaction: {}", self.explorer_link(&sig));
+                            println!(
+                                "  Bus: {} ({} ORE at selection)",
+                                bus,
+                                amount_u64_to_string(bus_rewards)
+                            );
+                        }
+                        if !self.no_sound_notification {
+                            utils::play_sound();
+                        }
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Best hash: {} (difficulty {})",
-            bs58::encode(best_hash.h).into_string(),
-            best_difficulty
-        ));
+                        last_difficulty = solution_difficulty;
 
-        Solution::new(best_hash.d, best_nonce.to_le_bytes())
-    }
+                        // Compound claimed rewards during the next round, if needed
+                        compound_due = true;
+                        break;
+                    }
 
-    // MI: reserve threads approach
-    async fn find_hash_par_threads(
-        proof: Proof,
-        cutoff_time: u64,
-        threads: u64,
-        min_difficulty: u32,
-        risk_time: u64,
-        checkpoint_step: u64,
-    ) -> Solution {
-        // Dispatch job to each thread
-        let progress_bar = Arc::new(spinner::new_progress_bar());
-        let global_best_difficulty = Arc::new(RwLock::new(0u32));
-        progress_bar.set_message("Mining...");
-        let handles: Vec<_> = (0..threads)
-            .map(|i| {
-                let global_best_difficulty = Arc::clone(&global_best_difficulty);
-                std::thread::spawn({
-                    let proof = proof.clone();
-                    let progress_bar = progress_bar.clone();
-                    let mut memory = equix::SolverMemory::new();
-                    move || {
-                        // Start hashing
-                        let timer = Instant::now();
-                        let mut nonce = u64::MAX.saturating_div(threads).saturating_mul(i);
-                        let mut best_nonce = nonce;
-                        let mut best_difficulty = 0;
-                        let mut best_hash = Hash::default();
-                        loop {
-                            // Create hash
-                            if let Ok(hx) = drillx::hash_with_memory(
-                                &mut memory,
-                                &proof.challenge,
-                                &nonce.to_le_bytes(),
-                            ) {
-                                let difficulty = hx.difficulty();
-                                if difficulty.gt(&best_difficulty) {
-                                    best_nonce = nonce;
-                                    best_difficulty = difficulty;
-                                    best_hash = hx;
-                                    // {{ edit_1 }}
-                                    if best_difficulty.gt(&*global_best_difficulty.read().unwrap())
-                                    {
-                                        *global_best_difficulty.write().unwrap() = best_difficulty;
-                                    }
-                                    // {{ edit_1 }}
-                                }
-                            }
+                    // MI: a better hash turned up before confirmation, resubmit with it
+                    Err(err) if is_replaced(&err) => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: false,
+                            signature: None,
+                            error: Some(err.to_string()),
+                        });
+                        if let Some((difficulty, improved)) = risk_window
+                            .as_ref()
+                            .and_then(|risk_window| risk_window.improved(solution_difficulty, 1))
+                        {
+                            println!(
+                                "Resubmitting with difficulty {} (was {})...",
+                                difficulty, solution_difficulty
+                            );
+                            events.emit(MinerEvent::SolutionFound {
+                                difficulty,
+                                hashes: 0,
+                                nonce: u64::from_le_bytes(improved.n),
+                            });
+                            replaced_difficulty.get_or_insert(solution_difficulty);
+                            solution = improved;
+                            solution_difficulty = difficulty;
+                        }
+                    }
 
-                            // Exit if time has elapsed
-                            if nonce % checkpoint_step == 0 {
-                                let global_best_difficulty =
-                                    *global_best_difficulty.read().unwrap();
-                                let current_timestamp = timer.elapsed().as_secs();
-                                if current_timestamp.ge(&cutoff_time) {
-                                    if global_best_difficulty.ge(&min_difficulty) {
-                                        // if min difficulty has been met
-                                        break;
-                                    } else {
-                                        // hashes for extra time after deadline (i.e. extra 29 secs)
-                                        if current_timestamp
-                                            .ge(&cutoff_time.saturating_add(risk_time))
-                                        {
-                                            break;
-                                        }
-                                        if i == 0 {
-                                            progress_bar.set_message(format!(
-                                                "Mining... ({} sec surpassed, difficulty {})",
-                                                current_timestamp.saturating_sub(cutoff_time),
-                                                global_best_difficulty,
-                                            ));
-                                        }
-                                    }
-                                } else if i == 0 {
-                                    progress_bar.set_message(format!(
-                                        "Mining... (difficulty {}, countdown {})",
-                                        global_best_difficulty,
-                                        format_duration(
-                                            cutoff_time.saturating_sub(current_timestamp) as u32
-                                        ),
-                                    ));
-                                }
-                            }
+                    // MI: the epoch ended, so bundle a reset and retry the same solution
+                    Err(err) if is_needs_reset(&err) && reset_attempts.lt(&MAX_RESET_ATTEMPTS) => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: false,
+                            signature: None,
+                            error: Some(err.to_string()),
+                        });
+                        reset_attempts += 1;
+                        config = get_config(&self.rpc_client).await;
+                        let latest_proof =
+                            get_proof_with_authority(&self.rpc_client, authority).await;
+                        if latest_proof.challenge.ne(&proof.challenge) {
+                            println!("Challenge changed. Mining a new solution...");
+                            break;
+                        }
+                        println!("Retrying with a reset instruction...");
+                        reset = true;
+                    }
+
+                    // MI: the next round's proof check tells whether it landed after all
+                    Err(err) if unconfirmed_signature(&err).is_some() => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: false,
+                            signature: None,
+                            error: Some(err.to_string()),
+                        });
+                        last_signature = unconfirmed_signature(&err);
+                        last_difficulty = solution_difficulty;
+                        println!(
+                            "{} Transaction unconfirmed, it may still land. Waiting for the proof to update...",
+                            "WARNING".bold().yellow(),
+                        );
+                        break;
+                    }
 
-                            // Increment nonce
-                            nonce += 1;
+                    // MI: the replacement fails when a replaced transaction landed first
+                    Err(err) if replaced_difficulty.is_some() => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: false,
+                            signature: None,
+                            error: Some(err.to_string()),
+                        });
+                        let latest_proof =
+                            get_proof_with_authority(&self.rpc_client, authority).await;
+                        if latest_proof.challenge.eq(&proof.challenge) {
+                            return Err(notify_stopped(
+                                format!("Mining stopped: {}", err),
+                                [slack_message_sender, discord_message_sender],
+                                heartbeat_task,
+                                messaging_tasks,
+                            )
+                            .await);
+                        }
+                        println!("  A replaced transaction landed first.");
+                        last_difficulty = replaced_difficulty.unwrap_or_default();
+                        rounds_submitted += 1;
+                        if let Some(stats) = heartbeat_stats.as_ref() {
+                            stats.lock().unwrap().rounds += 1;
+                        }
+                        if let Some(stats) = output_stats.as_ref() {
+                            stats.lock().unwrap().record_round(
+                                last_difficulty,
+                                *self.last_fee_lamports.read().unwrap(),
+                            );
                         }
+                        break;
+                    }
 
-                        // Return the best nonce
-                        (best_nonce, best_difficulty, best_hash)
+                    // MI: on other errors, exit loop to avoid hang-up
+                    Err(err) => {
+                        events.emit(MinerEvent::SubmissionResult {
+                            ok: false,
+                            signature: None,
+                            error: Some(err.to_string()),
+                        });
+                        return Err(notify_stopped(
+                            format!("Mining stopped: {}", err),
+                            [slack_message_sender, discord_message_sender],
+                            heartbeat_task,
+                            messaging_tasks,
+                        )
+                        .await);
                     }
-                })
-            })
-            .collect();
+                }
+            }
 
-        // Join handles and return best nonce
-        let mut best_nonce = 0;
-        let mut best_difficulty = 0;
-        let mut best_hash = Hash::default();
-        for h in handles {
-            if let Ok((nonce, difficulty, hash)) = h.join() {
-                if difficulty > best_difficulty {
-                    best_difficulty = difficulty;
-                    best_nonce = nonce;
-                    best_hash = hash;
+            if self.verbose {
+                timings.print();
+            }
+
+            // Stop hashing through the risk time once the round is settled
+            if let Some(risk_window) = risk_window.as_ref() {
+                risk_window.stop();
+                if risk_window.pipeline {
+                    pipelined_solution = risk_window
+                        .best()
+                        .map(|(difficulty, solution, _)| (proof.challenge, difficulty, solution));
                 }
             }
-        }
 
-        // Update log
-        progress_bar.finish_with_message(format!(
-            "Best hash: {} (difficulty {})",
-            bs58::encode(best_hash.h).into_string(),
-            best_difficulty
-        ));
+            if args.once {
+                return self
+                    .finish_once(&args, authority, proof, last_difficulty, last_signature)
+                    .await;
+            }
+        }
+    }
 
-        Solution::new(best_hash.d, best_nonce.to_le_bytes())
+    // MI: wait for the --once round to land and print its stats
+    async fn finish_once(
+        &self,
+        args: &MineArgs,
+        authority: Pubkey,
+        proof: Proof,
+        difficulty: u32,
+        signature: Option<Signature>,
+    ) -> Result<(), Error> {
+        let updated_proof = match signature {
+            Some(_) => {
+                get_updated_proof_with_authority(
+                    &self.rpc_client,
+                    authority,
+                    proof.last_hash_at,
+                    Duration::from_millis(args.challenge_refresh_interval.max(1)),
+                    PROOF_UPDATE_TIMEOUT,
+                )
+                .await
+            }
+            None => None,
+        };
+        let Some(updated_proof) = updated_proof else {
+            return Err(Error::Failed("The round did not land".to_string()));
+        };
+        println!("\n{}", "Round complete".bold());
+        println!("  Difficulty: {}", difficulty);
+        println!(
+            "  Reward: {} ORE",
+            amount_u64_to_string(updated_proof.balance.saturating_sub(proof.balance))
+        );
+        println!(
+            "  Stake: {} ORE",
+            amount_u64_to_string(updated_proof.balance)
+        );
+        if let Some(sig) = signature {
+            println!("  Transaction: {}", self.explorer_link(&sig));
+        }
+        Ok(())
     }
 
     // MI: since 2.0
@@ -552,17 +1731,155 @@ impl Miner {
             .le(&clock.unix_timestamp)
     }
 
-    async fn get_cutoff(&self, proof: Proof, buffer_time: u64) -> u64 {
+    // MI: seconds local time is ahead of the cluster clock. Warns past the threshold and
+    // logs when the drift changed since the previous check.
+    async fn check_clock_skew(&self, max_skew: u64, previous: Option<i64>) -> i64 {
+        let clock = get_clock(&self.rpc_client).await;
+        let local = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(clock.unix_timestamp);
+        let skew = local.saturating_sub(clock.unix_timestamp);
+        if skew.unsigned_abs().gt(&max_skew) {
+            println!(
+                "{} Local time is {} sec {} the cluster clock. Cutoffs follow the cluster clock, consider --slot-timing or adjusting --buffer-time.",
+                "WARNING".bold().yellow(),
+                skew.unsigned_abs(),
+                if skew.gt(&0) { "ahead of" } else { "behind" }
+            );
+        } else if let Some(previous) = previous {
+            if skew.abs_diff(previous).ge(&CLOCK_SKEW_LOG_DELTA) {
+                println!("Clock skew changed: {} sec -> {} sec", previous, skew);
+            }
+        }
+        skew
+    }
+
+    async fn get_cutoff(&self, proof: Proof, buffer_time: u64, slot_timing: bool) -> u64 {
         let clock = get_clock(&self.rpc_client).await;
+        let now = if slot_timing {
+            self.estimate_cluster_time(&clock).await
+        } else {
+            clock.unix_timestamp
+        };
         proof
             .last_hash_at
             .saturating_add(60)
             .saturating_sub(buffer_time as i64)
-            .saturating_sub(clock.unix_timestamp)
+            .saturating_sub(now)
             .max(0) as u64
     }
 
-    async fn find_bus(&self) -> Pubkey {
+    // MI: the clock sysvar can lag behind the cluster, so extrapolate from the latest
+    // processed slot and take the later (more conservative) of the two timestamps.
+    async fn estimate_cluster_time(&self, clock: &Clock) -> i64 {
+        let Ok(slot) = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::processed())
+            .await
+        else {
+            return clock.unix_timestamp;
+        };
+        let elapsed_ms = slot
+            .saturating_sub(clock.slot)
+            .saturating_mul(self.get_ms_per_slot().await);
+        let slot_time = clock
+            .unix_timestamp
+            .saturating_add(elapsed_ms.saturating_div(1_000) as i64);
+        let drift = slot_time.saturating_sub(clock.unix_timestamp);
+        if drift.gt(&1) {
+            println!(
+                "{} Clock sysvar lags slot-based time estimate by {} sec",
+                "WARNING".bold().yellow(),
+                drift
+            );
+        }
+        slot_time.max(clock.unix_timestamp)
+    }
+
+    async fn get_ms_per_slot(&self) -> u64 {
+        // Derive the slot duration from the most recent performance sample
+        if let Ok(samples) = self
+            .rpc_client
+            .get_recent_performance_samples(Some(1))
+            .await
+        {
+            if let Some(sample) = samples.first() {
+                if sample.num_slots.gt(&0) {
+                    return (sample.sample_period_secs as u64)
+                        .saturating_mul(1_000)
+                        .saturating_div(sample.num_slots);
+                }
+            }
+        }
+        DEFAULT_MS_PER_SLOT
+    }
+
+    // MI: ORE in the signer's associated token account, 0 without one. None if the RPC failed.
+    async fn wallet_ore_balance(&self) -> Option<u64> {
+        let signer = self.signer().ok()?;
+        let token_account_address = spl_associated_token_account::get_associated_token_address(
+            &signer.pubkey(),
+            &ore_api::consts::MINT_ADDRESS,
+        );
+        match self
+            .rpc_client
+            .get_token_account(&token_account_address)
+            .await
+        {
+            Ok(Some(token_account)) => {
+                u64::from_str(token_account.token_amount.amount.as_str()).ok()
+            }
+            Ok(None) => Some(0),
+            Err(_) => None,
+        }
+    }
+
+    // MI: stake claimed rewards that reached the wallet back into the proof. The ORE held
+    // before mining started is left alone, it is lowered if the wallet is spent below it.
+    // Returns the amount staked, which the proof balance gains without it being a reward.
+    async fn compound(
+        &self,
+        threshold: f64,
+        target_multiplier: Option<f64>,
+        baseline: &mut Option<u64>,
+    ) -> Result<u64, Error> {
+        let Some(balance) = self.wallet_ore_balance().await else {
+            return Ok(0);
+        };
+        let held = baseline.map_or(balance, |held| held.min(balance));
+        *baseline = Some(held);
+        let amount = balance.saturating_sub(held);
+        if amount.eq(&0) || amount.lt(&amount_f64_to_u64(threshold)) {
+            return Ok(0);
+        }
+
+        // Stake and show the effect on the multiplier
+        let config = get_config(&self.rpc_client).await;
+        let proof = get_proof_with_authority(&self.rpc_client, self.authority()?).await;
+        println!(
+            "Compounding {} ORE. Multiplier: {:12}x",
+            amount_u64_to_string(amount),
+            calculate_multiplier(proof.balance, config.top_balance)
+        );
+        let staked = self
+            .stake_amount(StakeArgs {
+                amount: Some(amount_u64_to_f64(amount)),
+                token_account: None,
+                from_rewards: false,
+                target_multiplier,
+                list_token_accounts: false,
+            })
+            .await?;
+        let proof = get_proof_with_authority(&self.rpc_client, self.authority()?).await;
+        println!(
+            "  Multiplier after compounding: {:12}x",
+            calculate_multiplier(proof.balance, config.top_balance)
+        );
+        Ok(staked)
+    }
+
+    async fn find_bus(&self) -> (Pubkey, u64) {
         // Fetch the bus with the largest balance
         if let Ok(accounts) = self.rpc_client.get_multiple_accounts(&BUS_ADDRESSES).await {
             let mut top_bus_balance: u64 = 0;
@@ -577,68 +1894,497 @@ impl Miner {
                     }
                 }
             }
-            return top_bus;
+            return (top_bus, top_bus_balance);
         }
 
-        // Otherwise return a random bus
+        // Otherwise return a random bus, with unknown rewards
         let i = rand::thread_rng().gen_range(0..BUS_COUNT);
-        BUS_ADDRESSES[i]
+        (BUS_ADDRESSES[i], 0)
+    }
+}
+
+// MI: the signer is the proof's miner and signs the tx, the authority owns the proof
+pub fn build_mine_ixs(
+    signer: Pubkey,
+    authority: Pubkey,
+    bus: Pubkey,
+    solution: Solution,
+    reset: bool,
+) -> Vec<Instruction> {
+    let mut ixs = vec![ore_api::instruction::auth(proof_pubkey(authority))];
+    if reset {
+        ixs.push(ore_api::instruction::reset(signer));
+    }
+    ixs.push(ore_api::instruction::mine(signer, authority, bus, solution));
+    ixs
+}
+
+// MI: the cores with the lowest ids, the default selection for --cores
+fn first_cores(available: &[CoreId], cores: u64) -> Vec<CoreId> {
+    available
+        .iter()
+        .filter(|core_id| (core_id.id as u64).lt(&cores))
+        .copied()
+        .collect()
+}
+
+// MI: resolve a --core-mask against the cores the OS reports
+fn select_core_mask(available: &[CoreId], mask: &[usize]) -> Result<Vec<CoreId>, String> {
+    let mut selected: Vec<CoreId> = vec![];
+    for id in mask {
+        let Some(core_id) = available.iter().find(|core_id| core_id.id.eq(id)) else {
+            return Err(format!(
+                "Core {} in --core-mask is not available (found {} cores)",
+                id,
+                available.len()
+            ));
+        };
+        if !selected.contains(core_id) {
+            selected.push(*core_id);
+        }
+    }
+    Ok(selected)
+}
+
+// MI: hashing threads with an optional custom stack size
+fn spawn_hashing_thread<F, T>(stack_size: Option<usize>, f: F) -> std::thread::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let mut builder = std::thread::Builder::new().name("ore-hasher".to_string());
+    if let Some(stack_size) = stack_size {
+        builder = builder.stack_size(stack_size);
+    }
+    builder.spawn(f).expect("Failed to spawn hashing thread")
+}
+
+// MI: run each round's job until the pool is dropped
+fn hashing_worker(
+    core_id: Option<CoreId>,
+    reporter: bool,
+    all_solutions: bool,
+    nice: Option<i32>,
+    jobs: std::sync::mpsc::Receiver<HashingJob>,
+) {
+    // Pin to core
+    if let Some(core_id) = core_id {
+        let _ = core_affinity::set_for_current(core_id);
+    }
+
+    // MI: on Linux the nice level applies to the calling thread only, so each hashing thread
+    // sets its own
+    if let Some(nice) = nice {
+        match utils::set_nice(nice) {
+            Ok(()) if reporter => println!("Hashing threads set to nice {}", nice),
+            Ok(()) => {}
+            Err(err) if reporter => println!(
+                "{} Failed to set nice level {}: {}",
+                "WARNING".bold().yellow(),
+                nice,
+                err
+            ),
+            Err(_) => {}
+        }
+    }
+    let mut memory = equix::SolverMemory::new();
+    while let Ok(job) = jobs.recv() {
+        let result = job.run(&mut memory, reporter, all_solutions);
+        let _ = job.results.send(result);
+    }
+}
+
+// Hashes after the cutoff are not counted in the returned total
+fn submit_early(progress_bar: &ProgressBar, risk_window: &RiskWindowHashing) -> (Solution, u64) {
+    let (difficulty, solution, hash) =
+        risk_window
+            .best()
+            .unwrap_or((0, Solution::new([0; 16], [0; 8]), [0; 32]));
+    finish_mining_progress_bar(
+        progress_bar,
+        format!(
+            "Best hash so far: {} (difficulty {}). Hashing on while it confirms...",
+            bs58::encode(hash).into_string(),
+            difficulty
+        ),
+    );
+    (solution, risk_window.hashes.load(Ordering::Relaxed))
+}
+
+// MI: a hidden progress bar keeps log files free of spinner output
+fn new_mining_progress_bar(quiet: bool) -> ProgressBar {
+    if quiet {
+        ProgressBar::hidden()
+    } else {
+        spinner::new_progress_bar()
+    }
+}
+
+fn finish_mining_progress_bar(progress_bar: &ProgressBar, msg: String) {
+    if progress_bar.is_hidden() {
+        println!("{}", msg);
+    } else {
+        progress_bar.finish_with_message(msg);
+    }
+}
+
+// Reward rate doubles with each difficulty level above the min, scaled by the stake multiplier
+pub fn estimate_reward(config: &Config, multiplier: f64, difficulty: u32) -> u64 {
+    let Some(levels) = difficulty.checked_sub(config.min_difficulty as u32) else {
+        return 0;
+    };
+    let reward = config
+        .base_reward_rate
+        .saturating_mul(2u64.saturating_pow(levels));
+    (reward as f64 * multiplier) as u64
+}
+
+// MI: the miner stops hashing at the expected min difficulty, even if the program needs more
+fn check_min_difficulty(expected_min_difficulty: u32, program_min_difficulty: u64) {
+    if (expected_min_difficulty as u64).lt(&program_min_difficulty) {
+        println!(
+            "{} Expected min difficulty ({}) is below the program min difficulty ({}). Submissions below {} will be rejected, consider raising --expected-min-difficulty.",
+            "WARNING".bold().yellow(),
+            expected_min_difficulty,
+            program_min_difficulty,
+            program_min_difficulty
+        );
     }
 }
 
-fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
+pub fn calculate_multiplier(balance: u64, top_balance: u64) -> f64 {
     1.0 + (balance as f64 / top_balance as f64).min(1.0f64)
 }
 
+// MI: the inverse of calculate_multiplier, the stake that reaches the target multiplier
+pub fn stake_for_multiplier(target_multiplier: f64, top_balance: u64) -> u64 {
+    ((target_multiplier.clamp(1.0, 2.0) - 1.0) * top_balance as f64).ceil() as u64
+}
+
 fn format_duration(seconds: u32) -> String {
     let minutes = seconds / 60;
     let remaining_seconds = seconds % 60;
     format!("{:02}:{:02}", minutes, remaining_seconds)
 }
 
-// MI
-async fn slack_messaging_system(
-    slack_webhook: String,
-    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
+// MI: mining activity since the last heartbeat
+#[derive(Default)]
+struct HeartbeatStats {
+    rounds: u64,
+    rewards: u64,
+    balance: u64,
+    hashes: u64,
+    hashing_time: Duration,
+}
+
+async fn heartbeat_system(
+    period: Duration,
+    stats: Arc<Mutex<HeartbeatStats>>,
+    slack_sender: Option<UnboundedSender<RewardsMessage>>,
+    discord_sender: Option<UnboundedSender<RewardsMessage>>,
 ) {
+    let mut interval = tokio::time::interval(period.max(Duration::from_secs(60)));
+    interval.tick().await;
     loop {
-        while let Some(slack_message) = receiver_channel.recv().await {
-            match slack_message {
-                RewardsMessage::Rewards(d, r, b) => {
-                    slack_messaging(slack_webhook.clone(), SrcType::Solo, d, r, b).await
-                }
-            }
+        interval.tick().await;
+        let (rounds, rewards, balance, hashpower) = {
+            let mut stats = stats.lock().unwrap();
+            let hashing_secs = stats.hashing_time.as_secs().max(1);
+            let summary = (
+                stats.rounds,
+                stats.rewards,
+                stats.balance,
+                stats.hashes.saturating_div(hashing_secs),
+            );
+            *stats = HeartbeatStats {
+                balance: stats.balance,
+                ..Default::default()
+            };
+            summary
+        };
+        for sender in [&slack_sender, &discord_sender].into_iter().flatten() {
+            let _ = sender.send(RewardsMessage::Heartbeat(
+                rounds, rewards, balance, hashpower,
+            ));
         }
     }
 }
 
-async fn discord_messaging_system(
-    discord_webhook: String,
-    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
-) {
+// MI: session totals for the --output-interval stats line
+#[derive(Default)]
+struct OutputStats {
+    rounds: u64,
+    difficulty_sum: u64,
+    best_difficulty: u32,
+    rewards: u64,
+    fees: u64,
+    hashes: u64,
+    hashing_time: Duration,
+}
+
+impl OutputStats {
+    fn record_round(&mut self, difficulty: u32, fee: u64) {
+        self.rounds += 1;
+        self.difficulty_sum += difficulty as u64;
+        self.best_difficulty = self.best_difficulty.max(difficulty);
+        self.fees += fee;
+    }
+}
+
+// The hashpower is measured over the last interval, everything else over the session
+async fn output_system(period: Duration, stats: Arc<Mutex<OutputStats>>) {
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+    let mut last_hashes = 0;
+    let mut last_hashing_time = Duration::ZERO;
     loop {
-        while let Some(discord_message) = receiver_channel.recv().await {
-            match discord_message {
-                RewardsMessage::Rewards(d, r, b) => {
-                    discord_messaging(discord_webhook.clone(), SrcType::Solo, d, r, b).await
+        interval.tick().await;
+        let line = {
+            let stats = stats.lock().unwrap();
+            let hashing_secs = stats
+                .hashing_time
+                .saturating_sub(last_hashing_time)
+                .as_secs()
+                .max(1);
+            let hashpower = stats
+                .hashes
+                .saturating_sub(last_hashes)
+                .saturating_div(hashing_secs);
+            last_hashes = stats.hashes;
+            last_hashing_time = stats.hashing_time;
+            format!(
+                "{}  Rounds: {}  Difficulty: {:.1} avg, {} best  Rewards: {} ORE  Hashpower: {} H/sec  Spent: {} SOL",
+                Local::now().format("%H:%M:%S"),
+                stats.rounds,
+                stats.difficulty_sum as f64 / stats.rounds.max(1) as f64,
+                stats.best_difficulty,
+                amount_u64_to_string(stats.rewards),
+                hashpower,
+                lamports_to_sol(stats.fees)
+            )
+        };
+        println!("{}", line);
+    }
+}
+
+// MI: where the time of a round went, to tell whether RPC latency or hashing is the bottleneck
+#[derive(Default)]
+struct RoundTimings {
+    config_fetch: Duration,
+    proof_fetch: Duration,
+    hashing: Duration,
+    fee_estimate: Duration,
+    submit: Duration,
+    confirmation: Duration,
+}
+
+impl RoundTimings {
+    // A round can send several times when it bundles a reset or replaces its solution
+    fn record_send(&mut self, elapsed: Duration, send: SendTimings) {
+        self.fee_estimate += send.fee_estimate;
+        self.submit += send.submit;
+        self.confirmation += elapsed
+            .saturating_sub(send.fee_estimate)
+            .saturating_sub(send.submit);
+    }
+
+    fn print(&self) {
+        println!("  Round timings:");
+        for (step, elapsed) in [
+            ("Config fetch", self.config_fetch),
+            ("Proof fetch", self.proof_fetch),
+            ("Hashing", self.hashing),
+            ("Fee estimation", self.fee_estimate),
+            ("Submit", self.submit),
+            ("Confirmation", self.confirmation),
+        ] {
+            println!("    {:<16}{:>8} ms", step, elapsed.as_millis());
+        }
+    }
+}
+
+// MI: sleep in short steps so a shutdown request ends the wait, true if it did
+async fn sleep_unless_shutdown(duration: Duration, shutdown: &AtomicBool) -> bool {
+    let deadline = Instant::now() + duration;
+    while !shutdown.load(Ordering::Relaxed) {
+        let Some(left) = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+        else {
+            return false;
+        };
+        tokio::time::sleep(left.min(SHUTDOWN_POLL_INTERVAL)).await;
+    }
+    true
+}
+
+// MI: a --once round that stops before it lands is a failure, otherwise stopping is clean
+fn stopped(args: &MineArgs) -> Result<(), Error> {
+    if args.once {
+        return Err(Error::Failed(
+            "Mining stopped before the round landed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// MI: tell the remote operator the rig stopped, and let the webhooks deliver it before returning
+// the error. The heartbeat task holds its own senders, so it is stopped for the channels to close.
+async fn notify_stopped(
+    text: String,
+    senders: [UnboundedSender<RewardsMessage>; 2],
+    heartbeat_task: Option<JoinHandle<()>>,
+    messaging_tasks: Vec<JoinHandle<()>>,
+) -> Error {
+    let text = utils::redact_urls(&text);
+    if let Some(heartbeat_task) = heartbeat_task {
+        heartbeat_task.abort();
+    }
+    for sender in senders {
+        let _ = sender.send(RewardsMessage::Error(text.clone()));
+    }
+    let _ = tokio::time::timeout(
+        ERROR_DELIVERY_TIMEOUT,
+        futures::future::join_all(messaging_tasks),
+    )
+    .await;
+    Error::Failed(text)
+}
+
+// MI: coalesce rewards messages that arrive within the min interval into one summary.
+// Errors go out right away, but the same error is sent at most once per repeat interval.
+struct MessageThrottle {
+    min_interval: Duration,
+    last_sent_at: Option<Instant>,
+    pending: Vec<(u32, u64, u64, String)>,
+    last_error: Option<(String, Instant)>,
+}
+
+impl MessageThrottle {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_sent_at: None,
+            pending: vec![],
+            last_error: None,
+        }
+    }
+
+    // Time left before pending messages may be sent
+    fn wait(&self) -> Option<Duration> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(self.last_sent_at.map_or(Duration::ZERO, |last_sent_at| {
+            self.min_interval.saturating_sub(last_sent_at.elapsed())
+        }))
+    }
+
+    fn take(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.last_sent_at = Some(Instant::now());
+        let text = format_rewards(SrcType::Solo, &self.pending);
+        self.pending.clear();
+        Some(text)
+    }
+
+    async fn next(
+        &mut self,
+        receiver_channel: &mut UnboundedReceiver<RewardsMessage>,
+    ) -> Option<String> {
+        loop {
+            let message = match self.wait() {
+                Some(wait) => tokio::select! {
+                    message = receiver_channel.recv() => message,
+                    _ = tokio::time::sleep(wait) => return self.take(),
+                },
+                None => receiver_channel.recv().await,
+            };
+            match message {
+                Some(RewardsMessage::Rewards(d, r, b, t)) => self.pending.push((d, r, b, t)),
+                Some(RewardsMessage::Heartbeat(n, r, b, h)) => {
+                    return Some(format!(
+                        "S: {}\nHeartbeat: {} rounds\nR: {}\nB: {}\nH: {} H/sec",
+                        SrcType::Solo,
+                        n,
+                        amount_u64_to_string(r),
+                        amount_u64_to_string(b),
+                        h
+                    ))
+                }
+                Some(RewardsMessage::Alert(text)) => {
+                    return Some(format!("S: {}\n{}", SrcType::Solo, text))
+                }
+                Some(RewardsMessage::Error(text)) => {
+                    let repeated = self.last_error.as_ref().is_some_and(|(last, sent_at)| {
+                        last.eq(&text) && sent_at.elapsed().lt(&ERROR_REPEAT_INTERVAL)
+                    });
+                    if !repeated {
+                        let message = format!("S: {}\nERROR: {}", SrcType::Solo, text);
+                        self.last_error = Some((text, Instant::now()));
+                        return Some(message);
+                    }
                 }
+                None => return self.take(),
             }
         }
     }
 }
 
+fn format_rewards(source: SrcType, rewards: &[(u32, u64, u64, String)]) -> String {
+    match rewards {
+        [(difficulty, reward, balance, tx_link)] => format!(
+            "S: {}\nD: {}\nR: {}\nB: {}\nT: {}",
+            source,
+            difficulty,
+            amount_u64_to_string(*reward),
+            amount_u64_to_string(*balance),
+            tx_link
+        ),
+        _ => {
+            let difficulty = rewards.iter().map(|(d, ..)| *d).max().unwrap_or(0);
+            let reward: u64 = rewards.iter().map(|(_, r, ..)| r).sum();
+            let (_, _, balance, tx_link) = rewards.last().cloned().unwrap_or_default();
+            format!(
+                "S: {}\nN: {} rounds\nD: {}\nR: {}\nB: {}\nT: {}",
+                source,
+                rewards.len(),
+                difficulty,
+                amount_u64_to_string(reward),
+                amount_u64_to_string(balance),
+                tx_link
+            )
+        }
+    }
+}
+
 // MI
-async fn slack_messaging(
+async fn slack_messaging_system(
     slack_webhook: String,
-    source: SrcType,
-    difficulty: u32,
-    rewards: f64,
-    balance: f64,
+    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
+    min_interval: Duration,
 ) {
-    let text = format!(
-        "S: {}\nD: {}\nR: {}\nB: {}",
-        source, difficulty, rewards, balance
-    );
+    let mut throttle = MessageThrottle::new(min_interval);
+    while let Some(text) = throttle.next(&mut receiver_channel).await {
+        slack_messaging(slack_webhook.clone(), text).await
+    }
+}
+
+async fn discord_messaging_system(
+    discord_webhook: String,
+    mut receiver_channel: UnboundedReceiver<RewardsMessage>,
+    min_interval: Duration,
+) {
+    let mut throttle = MessageThrottle::new(min_interval);
+    while let Some(text) = throttle.next(&mut receiver_channel).await {
+        discord_messaging(discord_webhook.clone(), text).await
+    }
+}
+
+// MI
+async fn slack_messaging(slack_webhook: String, text: String) {
     let slack_webhook_url =
         url::Url::parse(&slack_webhook).expect("Failed to parse slack webhook url");
     let message = SlackChannelMessage::builder().text(text).build();
@@ -648,7 +2394,10 @@ async fn slack_messaging(
     let mut num_retries = 0;
     loop {
         if let Err(err) = req.try_clone().unwrap().send().await {
-            eprintln!("Err sending slack webhook: {:?}", err);
+            eprintln!(
+                "Err sending slack webhook: {}",
+                utils::redact_urls(&err.to_string())
+            );
             // error!("Err sending slack webhook: {:?}", err);
             if num_retries < 3 {
                 println!("retry...");
@@ -663,18 +2412,7 @@ async fn slack_messaging(
     }
 }
 
-async fn discord_messaging(
-    discord_webhook: String,
-    source: SrcType,
-    difficulty: u32,
-    rewards: f64,
-    balance: f64,
-) {
-    let text = format!(
-        "S: {}\nD: {}\nR: {}\nB: {}",
-        source, difficulty, rewards, balance
-    );
-
+async fn discord_messaging(discord_webhook: String, text: String) {
     // You don't need a token when you are only dealing with webhooks.
     let http = Http::new("");
     let discord_webhook = Webhook::from_url(&http, &discord_webhook)
@@ -691,7 +2429,10 @@ async fn discord_messaging(
             .execute(&http, false, |w| w.content(&text).username("Mirabot"))
             .await
         {
-            eprintln!("Err sending discord webhook: {:?}", err);
+            eprintln!(
+                "Err sending discord webhook: {}",
+                utils::redact_urls(&err.to_string())
+            );
             // error!("Err sending discord webhook: {:?}", err);
             if num_retries < 3 {
                 println!("retry...");
@@ -705,3 +2446,66 @@ async fn discord_messaging(
         break;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solution() -> Solution {
+        Solution::new([1; 16], [2; 8])
+    }
+
+    #[test]
+    fn mine_ixs_are_signed_by_the_signer_for_the_authority_proof() {
+        let (signer, authority, bus) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), BUS_ADDRESSES[0]);
+        let ixs = build_mine_ixs(signer, authority, bus, solution(), false);
+        assert_eq!(
+            ixs,
+            vec![
+                ore_api::instruction::auth(proof_pubkey(authority)),
+                ore_api::instruction::mine(signer, authority, bus, solution()),
+            ]
+        );
+
+        // The signer signs the mine instruction, the proof it updates is the authority's
+        let mine_ix = ixs.last().unwrap();
+        assert!(mine_ix
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .all(|meta| meta.pubkey.eq(&signer)));
+        assert!(mine_ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey.eq(&proof_pubkey(authority))));
+        assert!(!mine_ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey.eq(&proof_pubkey(signer))));
+    }
+
+    #[tokio::test]
+    async fn a_shutdown_request_ends_the_sleep() {
+        let shutdown = AtomicBool::new(false);
+        assert!(!sleep_unless_shutdown(Duration::from_millis(10), &shutdown).await);
+
+        shutdown.store(true, Ordering::Relaxed);
+        let started_at = Instant::now();
+        assert!(sleep_unless_shutdown(Duration::from_secs(3600), &shutdown).await);
+        assert!(started_at.elapsed().lt(&SHUTDOWN_POLL_INTERVAL));
+    }
+
+    #[test]
+    fn reset_is_signed_by_the_signer() {
+        let (signer, authority, bus) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), BUS_ADDRESSES[0]);
+        let ixs = build_mine_ixs(signer, authority, bus, solution(), true);
+        assert_eq!(ixs.len(), 3);
+        assert_eq!(ixs[1], ore_api::instruction::reset(signer));
+        assert_eq!(
+            ixs[2],
+            ore_api::instruction::mine(signer, authority, bus, solution())
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+// MI: best hash found so far on the challenge
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct NonceBest {
+    pub difficulty: u32,
+    pub nonce: u64,
+    pub digest: [u8; 16],
+}
+
+// MI: where each hashing thread got to on a challenge, kept in the --nonce-file so a restart
+// resumes the search instead of covering the same nonces again
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NonceFile {
+    pub challenge: String,
+    pub nonces: Vec<u64>,
+    pub best: Option<NonceBest>,
+}
+
+impl NonceFile {
+    // The saved progress, if it was made on this challenge with the same number of threads
+    pub fn load(path: &str, challenge: &[u8; 32], threads: usize) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let nonce_file: Self = serde_json::from_str(&contents).ok()?;
+        (nonce_file
+            .challenge
+            .eq(&bs58::encode(challenge).into_string())
+            && nonce_file.nonces.len().eq(&threads))
+        .then_some(nonce_file)
+    }
+
+    pub fn save(&self, path: &str) {
+        let result = Path::new(path)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|err| err.to_string())
+            .and_then(|_| serde_json::to_string_pretty(self).map_err(|err| err.to_string()))
+            .and_then(|contents| std::fs::write(path, contents).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            println!(
+                "{} Failed to save nonce file {}: {}",
+                "WARNING".bold().yellow(),
+                path,
+                err
+            );
+        }
+    }
+}
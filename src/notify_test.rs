@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use colored::*;
+use serde_json::json;
+use slack_messaging::Message as SlackChannelMessage;
+
+use crate::{args::NotifyTestArgs, utils::redact_urls, Error, Miner};
+
+const NOTIFY_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+const NOTIFY_TEST_MESSAGE: &str =
+    "ore-cli test notification. Your webhook is set up, mining rewards will be posted here.";
+
+impl Miner {
+    // MI: send a sample message to each configured webhook, so a broken one shows up before
+    // a big hash goes unannounced
+    pub async fn notify_test(&self, _args: NotifyTestArgs) -> Result<(), Error> {
+        if self.slack_webhook.is_none() && self.discord_webhook.is_none() {
+            return Err(Error::Failed(
+                "No webhook configured. Set --slack-webhook or --discord-webhook, or ORE_SLACK_WEBHOOK or ORE_DISCORD_WEBHOOK.".to_string(),
+            ));
+        }
+
+        let client = reqwest::Client::new();
+        let mut failures = 0;
+        if let Some(slack_webhook) = self.slack_webhook.as_ref() {
+            let message = SlackChannelMessage::builder()
+                .text(NOTIFY_TEST_MESSAGE.to_string())
+                .build();
+            let request = client.post(slack_webhook).json(&message);
+            if !send_test(request, "Slack").await {
+                failures += 1;
+            }
+        }
+        if let Some(discord_webhook) = self.discord_webhook.as_ref() {
+            let message = json!({
+                "content": NOTIFY_TEST_MESSAGE,
+                "username": "Mirabot",
+            });
+            let request = client.post(discord_webhook).json(&message);
+            if !send_test(request, "Discord").await {
+                failures += 1;
+            }
+        }
+
+        if failures.gt(&0) {
+            return Err(Error::Failed(format!("{} webhook(s) failed", failures)));
+        }
+        println!(
+            "\n{} All webhooks accepted the test message",
+            "OK".bold().green()
+        );
+        Ok(())
+    }
+}
+
+// Send the request and print the HTTP status, returns whether the webhook accepted it
+async fn send_test(request: reqwest::RequestBuilder, label: &str) -> bool {
+    match request.timeout(NOTIFY_TEST_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => {
+            println!(
+                "  {:>4} {}: HTTP {}",
+                "OK".bold().green(),
+                label.bold(),
+                response.status()
+            );
+            true
+        }
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            println!(
+                "  {:>4} {}: HTTP {} {}",
+                "FAIL".bold().red(),
+                label.bold(),
+                status,
+                redact_urls(body.trim())
+            );
+            false
+        }
+        Err(err) => {
+            println!(
+                "  {:>4} {}: {}",
+                "FAIL".bold().red(),
+                label.bold(),
+                redact_urls(&err.to_string())
+            );
+            false
+        }
+    }
+}
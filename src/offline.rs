@@ -0,0 +1,121 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use colored::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+use crate::{
+    args::{SignArgs, SubmitArgs},
+    utils::get_latest_blockhash_with_retries,
+    Error, Miner,
+};
+
+// MI: air-gapped signing. `--offline <FILE>` writes an unsigned transaction, `sign` adds
+// signatures on the cold machine, and `submit` broadcasts the signed transaction.
+impl Miner {
+    pub async fn write_offline_transaction(
+        &self,
+        ixs: &[Instruction],
+        cu_limit: u32,
+        fee_payer: Pubkey,
+        path: &str,
+    ) -> Result<(), Error> {
+        let mut final_ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee.unwrap_or(0)),
+        ];
+        final_ixs.extend_from_slice(ixs);
+        let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer));
+        let Ok((blockhash, _slot)) = get_latest_blockhash_with_retries(&self.rpc_client).await
+        else {
+            return Err(Error::Failed(
+                "Failed to fetch a recent blockhash".to_string(),
+            ));
+        };
+        tx.message.recent_blockhash = blockhash;
+        write_transaction(path, &tx).map_err(Error::Failed)?;
+        println!("Unsigned transaction written to {}", path);
+        println!("Required signers:");
+        for signer in tx
+            .message
+            .account_keys
+            .iter()
+            .take(tx.message.header.num_required_signatures as usize)
+        {
+            println!("  {}", signer);
+        }
+        println!(
+            "Sign it with `ore sign {}` and broadcast with `ore submit` before the blockhash expires (~60 sec).",
+            path
+        );
+        Ok(())
+    }
+
+    pub async fn sign(&self, args: SignArgs) -> Result<(), Error> {
+        let mut tx = read_transaction(&args.input).map_err(Error::Failed)?;
+
+        // Sign with the keypair and the fee payer, whichever of them the transaction requires
+        let signer = self.signer()?;
+        let fee_payer = self.fee_payer()?;
+        let mut keypairs: Vec<&Keypair> = vec![&signer];
+        if fee_payer.pubkey().ne(&signer.pubkey()) {
+            keypairs.push(&fee_payer);
+        }
+        let required =
+            &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+        keypairs.retain(|keypair| required.contains(&keypair.pubkey()));
+        if keypairs.is_empty() {
+            return Err(Error::Failed(
+                "None of the configured keypairs is a signer of this transaction".to_string(),
+            ));
+        }
+        let blockhash = tx.message.recent_blockhash;
+        tx.try_partial_sign(&keypairs, blockhash)
+            .map_err(|err| Error::Failed(format!("Failed to sign the transaction: {}", err)))?;
+
+        let output = args.output.unwrap_or(args.input);
+        write_transaction(&output, &tx).map_err(Error::Failed)?;
+        if tx.is_signed() {
+            println!("Signed transaction written to {}", output);
+        } else {
+            println!(
+                "Partially signed transaction written to {}. More signatures are required.",
+                output
+            );
+        }
+        Ok(())
+    }
+
+    pub async fn submit(&self, args: SubmitArgs) -> Result<(), Error> {
+        let tx = read_transaction(&args.input).map_err(Error::Failed)?;
+        if !tx.is_signed() {
+            return Err(Error::Failed(
+                "Transaction is missing signatures. Run `ore sign` first.".to_string(),
+            ));
+        }
+        let sig = self
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner(&tx)
+            .await?;
+        println!("{} {}", "OK".bold().green(), self.explorer_link(&sig));
+        Ok(())
+    }
+}
+
+fn write_transaction(path: &str, tx: &Transaction) -> Result<(), String> {
+    let bytes = bincode::serialize(tx).map_err(|err| err.to_string())?;
+    std::fs::write(path, STANDARD.encode(bytes))
+        .map_err(|err| format!("Failed to write {}: {}", path, err))
+}
+
+fn read_transaction(path: &str) -> Result<Transaction, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path, err))?;
+    let bytes = STANDARD
+        .decode(contents.trim())
+        .map_err(|err| format!("Invalid base64 transaction: {}", err))?;
+    bincode::deserialize(&bytes).map_err(|err| format!("Invalid transaction: {}", err))
+}
@@ -1,22 +1,98 @@
-use solana_sdk::signature::Signer;
+use std::time::Duration;
 
-use crate::{send_and_confirm::ComputeBudget, utils::proof_pubkey, Miner};
+use colored::*;
+use ore_api::state::Proof;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::{
+    send_and_confirm::{is_insufficient_funds, transaction_fee, ComputeBudget},
+    utils::proof_pubkey,
+    Error, Miner,
+};
+
+// MI: how long to wait for a new proof account to show up, and how often to resend the open
+const OPEN_RETRIES: usize = 3;
+const PROOF_CHECKS: usize = 10;
+const PROOF_CHECK_DELAY: Duration = Duration::from_secs(1);
 
 impl Miner {
-    pub async fn open(&self) {
+    // Opens the proof account unless it exists
+    pub async fn open(&self) -> Result<(), Error> {
         // Return early if miner is already registered
-        let signer = self.signer();
-        let fee_payer = self.fee_payer();
+        let signer = self.signer()?;
+        let fee_payer = self.fee_payer()?;
         let proof_address = proof_pubkey(signer.pubkey());
         if self.rpc_client.get_account(&proof_address).await.is_ok() {
-            return;
+            return Ok(());
         }
 
         // Sign and send transaction.
         println!("Generating challenge...");
         let ix = ore_api::instruction::open(signer.pubkey(), signer.pubkey(), fee_payer.pubkey());
-        self.send_and_confirm(&[ix], ComputeBudget::Fixed(400_000), false, None)
+        for attempt in 1..=OPEN_RETRIES {
+            // MI: retrying cannot fix a rent shortfall, stop with what the fee payer needs
+            if let Err(err) = self
+                .send_and_confirm(
+                    std::slice::from_ref(&ix),
+                    ComputeBudget::Fixed(400_000),
+                    false,
+                    None,
+                )
+                .await
+            {
+                if is_insufficient_funds(&err) {
+                    return Err(self
+                        .insufficient_rent(&fee_payer, signer.pubkey().ne(&fee_payer.pubkey()))
+                        .await);
+                }
+            }
+
+            // MI: the open can fail without an error, so wait for the proof account itself
+            println!("Waiting for proof account {}...", proof_address);
+            for _ in 0..PROOF_CHECKS {
+                if self.rpc_client.get_account(&proof_address).await.is_ok() {
+                    println!("{} Proof account opened", "OK".bold().green());
+                    return Ok(());
+                }
+                tokio::time::sleep(PROOF_CHECK_DELAY).await;
+            }
+            if attempt.lt(&OPEN_RETRIES) {
+                println!(
+                    "{} Proof account not found after {} sec. Retrying open ({}/{})...",
+                    "WARNING".bold().yellow(),
+                    PROOF_CHECKS as u64 * PROOF_CHECK_DELAY.as_secs(),
+                    attempt + 1,
+                    OPEN_RETRIES
+                );
+            }
+        }
+        Err(Error::Failed(format!(
+            "Failed to open proof account {} after {} attempts",
+            proof_address, OPEN_RETRIES
+        )))
+    }
+
+    async fn insufficient_rent(&self, fee_payer: &Keypair, separate_fee_payer: bool) -> Error {
+        let fee_payer = fee_payer.pubkey();
+        let rent = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(8 + std::mem::size_of::<Proof>())
+            .await
+            .unwrap_or_default();
+        let num_signatures = if separate_fee_payer { 2 } else { 1 };
+        let needed = rent.saturating_add(transaction_fee(
+            num_signatures,
+            400_000,
+            self.priority_fee.unwrap_or(0),
+        ));
+        let balance = self
+            .rpc_client
+            .get_balance(&fee_payer)
             .await
-            .ok();
+            .map_or("unknown".to_string(), |balance| balance.to_string());
+        Error::InsufficientFunds(format!(
+            "Insufficient funds to open the proof account. It needs {} lamports for rent and fees, the fee payer {} has {} lamports.",
+            needed, fee_payer, balance
+        ))
     }
 }
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use clap::{parser::ValueSource, ArgMatches};
+use serde::Deserialize;
+
+use crate::{Args, Commands};
+
+// MI: ore-cli specific settings, layered between built-in defaults and command line flags
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct OreConfig {
+    pub priority_fee: Option<u64>,
+    pub priority_fee_cap: Option<u64>,
+    pub dynamic_fee: Option<bool>,
+    pub dynamic_fee_url: Option<String>,
+    pub slack_webhook: Option<String>,
+    pub discord_webhook: Option<String>,
+    pub cores: Option<u64>,
+    pub buffer_time: Option<u64>,
+}
+
+impl OreConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|err| err.to_string()),
+            _ => toml::from_str(&contents).map_err(|err| err.to_string()),
+        }
+    }
+
+    // Fill in every setting that was not given on the command line
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) {
+        if let Some(priority_fee) = self.priority_fee {
+            if !from_command_line(matches, "priority_fee") {
+                args.priority_fee = Some(priority_fee);
+            }
+        }
+        if let Some(priority_fee_cap) = self.priority_fee_cap {
+            if !from_command_line(matches, "priority_fee_cap") {
+                args.priority_fee_cap = Some(priority_fee_cap);
+            }
+        }
+        if let Some(dynamic_fee) = self.dynamic_fee {
+            if !from_command_line(matches, "dynamic_fee") {
+                args.dynamic_fee = dynamic_fee;
+            }
+        }
+        if args.dynamic_fee_url.is_empty() {
+            args.dynamic_fee_url = self.dynamic_fee_url.into_iter().collect();
+        }
+        if args.slack_webhook.is_none() {
+            args.slack_webhook = self.slack_webhook;
+        }
+        if args.discord_webhook.is_none() {
+            args.discord_webhook = self.discord_webhook;
+        }
+        if let Commands::Mine(mine_args) = &mut args.command {
+            if mine_args.cores.is_none() && mine_args.threads.is_none() {
+                mine_args.cores = self.cores;
+            }
+            if let Some(buffer_time) = self.buffer_time {
+                if !from_command_line(matches, "buffer_time") {
+                    mine_args.buffer_time = buffer_time;
+                }
+            }
+        }
+    }
+}
+
+// Global args may be parsed at the top level or by the subcommand
+fn from_command_line(matches: &ArgMatches, id: &str) -> bool {
+    let is_cli = |matches: &ArgMatches| {
+        matches!(
+            matches
+                .try_get_raw(id)
+                .ok()
+                .and_then(|_| matches.value_source(id)),
+            Some(ValueSource::CommandLine)
+        )
+    };
+    is_cli(matches) || matches.subcommand().is_some_and(|(_, sub)| is_cli(sub))
+}
@@ -1,31 +1,34 @@
 use std::str::FromStr;
 
-use ore_api::consts::TOKEN_DECIMALS;
+use colored::*;
+use ore_api::state::Proof;
+use ore_utils::AccountDeserialize;
+use serde_json::json;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Signer;
-use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::ProofArgs,
-    utils::{get_proof, proof_pubkey},
-    Miner,
+    utils::{amount_u64_to_string, proof_pubkey, require_proof_at},
+    Error, Miner, OutputFormat,
 };
 
+// Max accounts per getMultipleAccounts request
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
 impl Miner {
-    pub async fn proof(&self, args: ProofArgs) {
-        let signer = self.signer();
-        let address = if let Some(address) = args.address {
-            Pubkey::from_str(&address).unwrap()
-        } else {
-            proof_pubkey(signer.pubkey())
+    pub async fn proof(&self, args: ProofArgs) -> Result<(), Error> {
+        if let Some(authorities) = args.authorities.as_ref() {
+            return self.proofs(authorities, args.sort_by_balance).await;
+        }
+        let address = match args.address {
+            Some(address) => Pubkey::from_str(&address)
+                .map_err(|_| Error::Failed(format!("Invalid address: {}", address)))?,
+            None => proof_pubkey(self.query_authority()?),
         };
-        let proof = get_proof(&self.rpc_client, address).await;
+        let proof = require_proof_at(&self.rpc_client, address).await?;
         println!("Address: {:?}", address);
         println!("Authority: {:?}", proof.authority);
-        println!(
-            "Balance: {:?} ORE",
-            amount_to_ui_amount(proof.balance, TOKEN_DECIMALS)
-        );
+        println!("Balance: {} ORE", amount_u64_to_string(proof.balance));
         println!(
             "Last hash: {}",
             solana_sdk::hash::Hash::new_from_array(proof.last_hash).to_string()
@@ -35,8 +38,100 @@ impl Miner {
         println!("Miner: {:?}", proof.miner);
         println!("Total hashes: {:?}", proof.total_hashes);
         println!(
-            "Total rewards: {:?} ORE",
-            amount_to_ui_amount(proof.total_rewards, TOKEN_DECIMALS)
+            "Total rewards: {} ORE",
+            amount_u64_to_string(proof.total_rewards)
         );
+        Ok(())
+    }
+
+    // MI: one snapshot of many wallets, fetched in batches instead of one request per proof
+    async fn proofs(&self, authorities: &str, sort_by_balance: bool) -> Result<(), Error> {
+        let list = std::fs::read_to_string(authorities)
+            .map(|contents| {
+                contents
+                    .lines()
+                    .map(|line| {
+                        line.split('#')
+                            .next()
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_else(|_| {
+                authorities
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect()
+            });
+        let mut pubkeys = vec![];
+        for authority in list.iter().filter(|authority| !authority.is_empty()) {
+            let pubkey = Pubkey::from_str(authority)
+                .map_err(|_| Error::Failed(format!("Invalid authority: {}", authority)))?;
+            pubkeys.push(pubkey);
+        }
+
+        // Fetch proofs
+        let mut proofs: Vec<(Pubkey, Option<Proof>)> = vec![];
+        for chunk in pubkeys.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let addresses: Vec<Pubkey> = chunk.iter().map(|a| proof_pubkey(*a)).collect();
+            let accounts = self
+                .rpc_client
+                .get_multiple_accounts(&addresses)
+                .await
+                .map_err(|err| Error::Failed(format!("Failed to fetch proof accounts: {}", err)))?;
+            for (authority, account) in chunk.iter().zip(accounts) {
+                let proof =
+                    account.and_then(|account| Proof::try_from_bytes(&account.data).ok().copied());
+                proofs.push((*authority, proof));
+            }
+        }
+        if sort_by_balance {
+            proofs.sort_by_key(|(_, proof)| {
+                std::cmp::Reverse(proof.map_or(0, |proof| proof.balance))
+            });
+        }
+
+        match self.output {
+            OutputFormat::Json => {
+                let rows: Vec<_> = proofs
+                    .iter()
+                    .map(|(authority, proof)| match proof {
+                        Some(proof) => json!({
+                            "authority": authority.to_string(),
+                            "balance": proof.balance,
+                            "last_hash_difficulty": drillx::difficulty(proof.last_hash),
+                            "last_hash_at": proof.last_hash_at,
+                        }),
+                        None => json!({
+                            "authority": authority.to_string(),
+                            "proof": null,
+                        }),
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+            }
+            OutputFormat::Text => {
+                let header = format!(
+                    "{:<44}  {:>20}  {:>10}  {:>12}",
+                    "Authority", "Balance (ORE)", "Difficulty", "Last hash at"
+                );
+                println!("{}", header.bold());
+                for (authority, proof) in proofs.iter() {
+                    match proof {
+                        Some(proof) => println!(
+                            "{:<44}  {:>20}  {:>10}  {:>12}",
+                            authority.to_string(),
+                            amount_u64_to_string(proof.balance),
+                            drillx::difficulty(proof.last_hash),
+                            proof.last_hash_at
+                        ),
+                        None => println!("{:<44}  {:>20}", authority.to_string(), "no proof"),
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
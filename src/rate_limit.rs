@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+// MI: token bucket refilled at `rate` requests per second, bursting up to one second's worth
+pub struct RateLimiter {
+    rate: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32) -> Self {
+        let rate = rate.max(1) as f64;
+        Self {
+            rate,
+            bucket: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    // Wait until a request may be sent
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let (tokens, last_refill) = *bucket;
+                let now = Instant::now();
+                let tokens = (tokens + now.duration_since(last_refill).as_secs_f64() * self.rate)
+                    .min(self.rate);
+                if tokens.ge(&1.0) {
+                    *bucket = (tokens - 1.0, now);
+                    return;
+                }
+                *bucket = (tokens, now);
+                Duration::from_secs_f64((1.0 - tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
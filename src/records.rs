@@ -0,0 +1,103 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::Local;
+use colored::*;
+use drillx::Solution;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use crate::{args::RecordsArgs, Error, Miner, OutputFormat};
+
+// MI: best solution ever found by a wallet
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Record {
+    pub difficulty: u32,
+    pub hash: String,
+    pub nonce: u64,
+    pub found_at: String,
+}
+
+// MI: personal bests keyed by signer pubkey, kept in ~/.config/ore/records.json
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Records(BTreeMap<String, Record>);
+
+impl Records {
+    pub fn path() -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        Some(
+            PathBuf::from(home)
+                .join(".config")
+                .join("ore")
+                .join("records.json"),
+        )
+    }
+
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("No home directory found")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    // Store the solution if it beats the wallet's record. Returns the previous best on a new record.
+    pub fn update(wallet: Pubkey, solution: &Solution) -> Option<u32> {
+        let hash = solution.to_hash();
+        let difficulty = hash.difficulty();
+        let mut records = Self::load();
+        let previous = records.0.get(&wallet.to_string()).map(|r| r.difficulty);
+        if previous.is_some_and(|best| difficulty.le(&best)) {
+            return None;
+        }
+        records.0.insert(
+            wallet.to_string(),
+            Record {
+                difficulty,
+                hash: Hash::new_from_array(hash.h).to_string(),
+                nonce: u64::from_le_bytes(solution.n),
+                found_at: Local::now().to_rfc3339(),
+            },
+        );
+        if let Err(err) = records.save() {
+            println!(
+                "{} Failed to save records: {}",
+                "WARNING".bold().yellow(),
+                err
+            );
+        }
+        Some(previous.unwrap_or(0))
+    }
+}
+
+impl Miner {
+    pub async fn records(&self, _args: RecordsArgs) -> Result<(), Error> {
+        let records = Records::load();
+        match self.output {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&records).unwrap());
+            }
+            OutputFormat::Text => {
+                if records.0.is_empty() {
+                    println!("No records yet. Start mining to set one!");
+                    return Ok(());
+                }
+                for (wallet, record) in records.0.iter() {
+                    println!("{}", wallet.bold());
+                    println!("  Difficulty: {}", record.difficulty);
+                    println!("  Hash: {}", record.hash);
+                    println!("  Nonce: {}", record.nonce);
+                    println!("  Found at: {}", record.found_at);
+                }
+            }
+        }
+        Ok(())
+    }
+}
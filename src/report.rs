@@ -0,0 +1,82 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::utils::redact_urls;
+
+// Reports kept while the server is slow or down, the oldest are dropped first
+const REPORT_QUEUE_SIZE: usize = 100;
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+const REPORT_SECRET_HEADER: &str = "X-Ore-Report-Secret";
+
+// MI: one landed round, POSTed as JSON to --report-url
+#[derive(Serialize, Debug)]
+pub struct RoundReport {
+    pub wallet: String,
+    pub difficulty: u32,
+    pub reward: f64,
+    pub fee_lamports: u64,
+    pub signature: String,
+    pub hashrate: f64,
+    pub timestamp: i64,
+}
+
+// MI: posts round reports from a background task, so a slow server never stalls mining
+pub struct Reporter {
+    queue: Arc<Mutex<VecDeque<RoundReport>>>,
+    notify: Arc<Notify>,
+}
+
+impl Reporter {
+    pub fn spawn(url: String, secret: Option<String>) -> Self {
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let notify = Arc::new(Notify::new());
+        tokio::spawn(report_system(url, secret, queue.clone(), notify.clone()));
+        Self { queue, notify }
+    }
+
+    pub fn report(&self, report: RoundReport) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len().ge(&REPORT_QUEUE_SIZE) {
+            queue.pop_front();
+        }
+        queue.push_back(report);
+        self.notify.notify_one();
+    }
+}
+
+async fn report_system(
+    url: String,
+    secret: Option<String>,
+    queue: Arc<Mutex<VecDeque<RoundReport>>>,
+    notify: Arc<Notify>,
+) {
+    let client = reqwest::Client::new();
+    loop {
+        notify.notified().await;
+        loop {
+            let report = queue.lock().unwrap().pop_front();
+            let Some(report) = report else {
+                break;
+            };
+            let mut request = client.post(&url).timeout(REPORT_TIMEOUT).json(&report);
+            if let Some(secret) = secret.as_ref() {
+                request = request.header(REPORT_SECRET_HEADER, secret);
+            }
+            match request.send().await {
+                Ok(response) if !response.status().is_success() => {
+                    eprintln!("Report rejected by the server: {}", response.status());
+                }
+                Err(err) => {
+                    eprintln!("Failed to send report: {}", redact_urls(&err.to_string()));
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+}
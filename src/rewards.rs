@@ -1,10 +1,10 @@
 use crate::{
     utils::{amount_u64_to_string, get_config},
-    Miner,
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn rewards(&self) {
+    pub async fn rewards(&self) -> Result<(), Error> {
         let config = get_config(&self.rpc_client).await;
         let base_reward_rate = config.base_reward_rate;
 
@@ -27,5 +27,6 @@ impl Miner {
             );
         }
         println!("{}", s);
+        Ok(())
     }
 }
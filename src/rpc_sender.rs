@@ -0,0 +1,264 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_rpc_client::http_sender::HttpSender;
+
+use crate::{rate_limit::RateLimiter, utils::redact_url};
+
+// MI: http sender for long sessions. Requests are paced through an optional rate limiter,
+// and the connection is rebuilt after a run of consecutive transport failures.
+// 429 responses are retried by the http sender, which honors the Retry-After header.
+pub struct MinerRpcSender {
+    url: String,
+    inner: RwLock<Arc<HttpSender>>,
+    limiter: Option<RateLimiter>,
+    reconnect_after: usize,
+    failures: AtomicUsize,
+    timeout: Duration,
+    pool_size: Option<usize>,
+}
+
+impl MinerRpcSender {
+    pub fn new(
+        url: String,
+        rate_limit: Option<u32>,
+        reconnect_after: usize,
+        timeout: Duration,
+        pool_size: Option<usize>,
+    ) -> Self {
+        Self {
+            inner: RwLock::new(Arc::new(http_sender(&url, timeout, pool_size))),
+            url,
+            limiter: rate_limit.map(RateLimiter::new),
+            reconnect_after,
+            failures: AtomicUsize::new(0),
+            timeout,
+            pool_size,
+        }
+    }
+
+    fn reconnect(&self) {
+        *self.inner.write().unwrap() =
+            Arc::new(http_sender(&self.url, self.timeout, self.pool_size));
+        self.failures.store(0, Ordering::Relaxed);
+        println!(
+            "RPC connection reset after repeated failures. Reconnected to {}",
+            redact_url(&self.url)
+        );
+    }
+}
+
+// Each request gives up after the timeout, idle connections are closed after it as well
+fn http_sender(url: &str, timeout: Duration, pool_size: Option<usize>) -> HttpSender {
+    let mut builder = solana_reqwest::Client::builder()
+        .timeout(timeout)
+        .pool_idle_timeout(timeout);
+    if let Some(pool_size) = pool_size {
+        builder = builder.pool_max_idle_per_host(pool_size);
+    }
+    let client = builder.build().expect("Failed to build RPC http client");
+    HttpSender::new_with_client(url, client)
+}
+
+#[async_trait]
+impl RpcSender for MinerRpcSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        if let Some(limiter) = self.limiter.as_ref() {
+            limiter.acquire().await;
+        }
+        let inner = self.inner.read().unwrap().clone();
+        let result = inner.send(request, params).await;
+        match &result {
+            // Only transport failures point at a stale connection
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ClientErrorKind::Reqwest(_) | ClientErrorKind::Io(_)
+                ) =>
+            {
+                let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+                if self.reconnect_after.gt(&0) && failures.ge(&self.reconnect_after) {
+                    self.reconnect();
+                }
+            }
+            _ => self.failures.store(0, Ordering::Relaxed),
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.read().unwrap().get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+// MI: scripted RPC responses for tests. Each request answers from its script in order, then
+// falls back to the default response of the solana mock sender.
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::{
+        collections::{HashMap, VecDeque},
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::{json, Value};
+    use solana_client::{
+        client_error::Result as ClientResult,
+        nonblocking::rpc_client::RpcClient,
+        rpc_client::RpcClientConfig,
+        rpc_request::RpcRequest,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+    use solana_rpc_client::mock_sender::MockSender;
+    use solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{write_keypair_file, Keypair},
+        signer::Signer,
+        transaction::Transaction,
+    };
+
+    // Number of requests sent per method, and the transactions submitted
+    #[derive(Clone, Default)]
+    pub struct Calls {
+        counts: Arc<Mutex<HashMap<RpcRequest, usize>>>,
+        transactions: Arc<Mutex<Vec<Transaction>>>,
+    }
+
+    impl Calls {
+        pub fn count(&self, request: RpcRequest) -> usize {
+            self.counts
+                .lock()
+                .unwrap()
+                .get(&request)
+                .copied()
+                .unwrap_or(0)
+        }
+
+        pub fn transactions(&self) -> Vec<Transaction> {
+            self.transactions.lock().unwrap().clone()
+        }
+    }
+
+    pub struct ScriptedSender {
+        replies: Mutex<HashMap<RpcRequest, VecDeque<ClientResult<Value>>>>,
+        calls: Calls,
+        fallback: MockSender,
+    }
+
+    impl ScriptedSender {
+        pub fn new() -> Self {
+            Self {
+                replies: Mutex::new(HashMap::new()),
+                calls: Calls::default(),
+                fallback: MockSender::new("succeeds"),
+            }
+        }
+
+        pub fn reply(self, request: RpcRequest, reply: ClientResult<Value>) -> Self {
+            self.replies
+                .lock()
+                .unwrap()
+                .entry(request)
+                .or_default()
+                .push_back(reply);
+            self
+        }
+
+        // A wallet that passes the balance check of send_and_confirm
+        pub fn funded(self) -> Self {
+            self.reply(
+                RpcRequest::GetBalance,
+                Ok(with_context(json!(1_000_000_000))),
+            )
+        }
+
+        pub fn client(self, commitment: CommitmentConfig) -> (RpcClient, Calls) {
+            let calls = self.calls.clone();
+            let client = RpcClient::new_sender(self, RpcClientConfig::with_commitment(commitment));
+            (client, calls)
+        }
+    }
+
+    #[async_trait]
+    impl RpcSender for ScriptedSender {
+        async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+            *self
+                .calls
+                .counts
+                .lock()
+                .unwrap()
+                .entry(request)
+                .or_default() += 1;
+            if request.eq(&RpcRequest::SendTransaction) {
+                // send_and_confirm submits base64 encoded legacy transactions
+                let tx = params[0]
+                    .as_str()
+                    .and_then(|encoded| STANDARD.decode(encoded).ok())
+                    .and_then(|bytes| bincode::deserialize(&bytes).ok());
+                if let Some(tx) = tx {
+                    self.calls.transactions.lock().unwrap().push(tx);
+                }
+            }
+            let reply = self
+                .replies
+                .lock()
+                .unwrap()
+                .get_mut(&request)
+                .and_then(|replies| replies.pop_front());
+            match reply {
+                Some(reply) => reply,
+                None => self.fallback.send(request, params).await,
+            }
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "scripted".to_string()
+        }
+    }
+
+    pub fn with_context(value: Value) -> Value {
+        json!({ "context": { "slot": 1 }, "value": value })
+    }
+
+    // getSignatureStatuses for a single signature, None while it is unknown to the cluster
+    pub fn signature_status(confirmation_status: Option<&str>) -> Value {
+        let status = confirmation_status.map(|confirmation_status| {
+            json!({
+                "slot": 1,
+                "confirmations": null,
+                "err": null,
+                "status": { "Ok": null },
+                "confirmationStatus": confirmation_status,
+            })
+        });
+        with_context(json!([status]))
+    }
+
+    // Writes the keypair to a temporary file for the keypair_filepath of a Miner
+    pub fn keypair_file(keypair: &Keypair) -> String {
+        let path = std::env::temp_dir().join(format!("ore-cli-test-{}.json", keypair.pubkey()));
+        write_keypair_file(keypair, &path).unwrap();
+        path.to_string_lossy().to_string()
+    }
+}
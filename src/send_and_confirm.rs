@@ -1,7 +1,11 @@
-use std::time::Duration;
+use std::{
+    fmt, io,
+    time::{Duration, Instant},
+};
 
 use chrono::Local;
 use colored::*;
+use futures::stream::{FuturesUnordered, StreamExt};
 use indicatif::ProgressBar;
 use ore_api::error::OreError;
 use solana_client::{
@@ -9,32 +13,93 @@ use solana_client::{
     rpc_config::RpcSendTransactionConfig,
 };
 use solana_program::{
-    instruction::Instruction,
+    address_lookup_table::AddressLookupTableAccount,
+    instruction::{Instruction, InstructionError},
     native_token::{lamports_to_sol, sol_to_lamports},
     // system_instruction::transfer, pubkey::Pubkey,
 };
-use solana_rpc_client::spinner;
+use solana_rpc_client::{rpc_client::SerializableTransaction, spinner};
 use solana_sdk::{
     commitment_config::CommitmentLevel,
     compute_budget::ComputeBudgetInstruction,
-    signature::{Signature, Signer},
-    transaction::Transaction,
+    hash::Hash,
+    message::{v0, VersionedMessage},
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
 };
 use solana_transaction_status::{TransactionConfirmationStatus, UiTransactionEncoding};
 
-use crate::Miner;
-use crate::{mine::DifficultyPayload, utils::get_latest_blockhash_with_retries};
+use crate::{hints, Miner};
+use crate::{
+    mine::DifficultyPayload,
+    utils::{get_latest_blockhash_with_retries, redact_url, redact_urls, MEMO_PROGRAM_ID},
+};
 
-const MIN_SOL_BALANCE: f64 = 0.005;
+pub const MIN_SOL_BALANCE: f64 = 0.005;
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
 
 const RPC_RETRIES: usize = 0;
 const _SIMULATION_RETRIES: usize = 4;
 const GATEWAY_RETRIES: usize = 150;
+const BLOCKHASH_REFRESH_RETRIES: usize = 5;
 const CONFIRM_RETRIES: usize = 8; // MI, 1 in version
+                                  // MI: finalization trails confirmation by about 32 slots, roughly 13 seconds
+const FINALIZED_CONFIRM_RETRIES: usize = 40;
 
 const CONFIRM_DELAY: u64 = 500; // MI, 0 in version 1
 const GATEWAY_DELAY: u64 = 0; // MI, 300 in version 1
 
+// MI: escalation ceiling when no --priority-fee-cap is set, the same as for extra fee difficulty
+const MAX_ESCALATED_PRIORITY_FEE: u64 = 300_000;
+
+const UNCONFIRMED_CHECKS: usize = 5;
+const UNCONFIRMED_CHECK_DELAY: u64 = 1_000;
+
+// MI: send_and_confirm gave up on a transaction that did not fail on chain. Carried as the
+// source of an io error, since ClientErrorKind has no typed custom variant.
+#[derive(Debug)]
+pub enum SendError {
+    // Not confirmed in time, the last signature sent may still land
+    Unconfirmed(Signature),
+    // Abandoned for a better solution, see --submit-only-if-improves
+    Replaced,
+    // Not sent, the fee payer holds less than MIN_SOL_BALANCE
+    InsufficientBalance(String),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Unconfirmed(sig) => write!(f, "Unconfirmed, may still land: {}", sig),
+            SendError::Replaced => write!(f, "Replaced by an improved solution"),
+            SendError::InsufficientBalance(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+impl From<SendError> for ClientError {
+    fn from(err: SendError) -> Self {
+        ClientErrorKind::Io(io::Error::other(err)).into()
+    }
+}
+
+pub(crate) fn send_error(err: &ClientError) -> Option<&SendError> {
+    match err.kind() {
+        ClientErrorKind::Io(err) => err.get_ref()?.downcast_ref(),
+        _ => None,
+    }
+}
+
+// MI: time spent estimating fees and sending in the last send_and_confirm, the rest of it
+// is spent waiting for confirmation
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SendTimings {
+    pub fee_estimate: Duration,
+    pub submit: Duration,
+}
+
 pub enum ComputeBudget {
     #[allow(dead_code)]
     Dynamic,
@@ -49,31 +114,51 @@ impl Miner {
         skip_confirm: bool,
         difficulty_payload: Option<DifficultyPayload>, // MI
     ) -> ClientResult<Signature> {
+        // Return error, if balance is zero
+        self.check_balance().await?;
+
+        // MI: send mine transactions as v0 with the --lookup-table, legacy otherwise
+        let mut lookup_table = match difficulty_payload {
+            Some(_) => self.load_lookup_table().await,
+            None => None,
+        };
+        let mut versioned_tx = None;
+
         let progress_bar = spinner::new_progress_bar();
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
-        let fee_payer = self.fee_payer();
-        let send_client = self.rpc_client.clone();
-
-        // Return error, if balance is zero
-        self.check_balance().await;
+        let fee_payer = self.fee_payer()?;
 
         // Set compute budget
         let mut final_ixs = vec![];
-        match compute_budget {
+        let cu_limit = match compute_budget {
             ComputeBudget::Dynamic => {
                 // final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(1_400_000))
                 todo!("simulate tx")
             }
-            ComputeBudget::Fixed(cus) => {
-                final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cus))
-            }
-        }
+            ComputeBudget::Fixed(cus) => cus,
+        };
+        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu_limit));
 
         // Set compute unit price
-        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-            self.priority_fee.unwrap_or(0),
-        ));
+        let num_signatures = if signer.pubkey() == fee_payer.pubkey() {
+            1
+        } else {
+            2
+        };
+        let max_priority_fee = [
+            difficulty_payload
+                .as_ref()
+                .and_then(|payload| payload.max_priority_fee),
+            self.max_total_fee_lamports
+                .map(|max_total_fee| max_cu_price(max_total_fee, num_signatures, cu_limit)),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let mut cu_price = clamp_priority_fee(self.priority_fee.unwrap_or(0), max_priority_fee);
+        let mut base_cu_price = cu_price;
+        final_ixs.push(ComputeBudgetInstruction::set_compute_unit_price(cu_price));
 
         // Add in user instructions
         final_ixs.extend_from_slice(ixs);
@@ -119,53 +204,125 @@ impl Miner {
         let mut tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
 
         // Submit tx
+        *self.last_send_timings.write().unwrap() = SendTimings::default();
+        *self.last_fee_capped.write().unwrap() = false;
+        let started_at = Instant::now();
+        let mut signatures = vec![];
+        let mut cu_prices = vec![];
         let mut attempts = 0;
+        let mut blockhash = Hash::default();
+        let mut refresh_blockhash = false;
+        let mut blockhash_refreshes = 0;
+        let escalation_cap = clamp_priority_fee(
+            self.priority_fee_cap.unwrap_or(MAX_ESCALATED_PRIORITY_FEE),
+            max_priority_fee,
+        );
+        let mut fee_escalation: u64 = 0;
+        let mut escalate = false;
         loop {
             progress_bar.set_message(format!("Submitting transaction... (attempt {})", attempts,));
 
-            // Sign tx with a new blockhash (after approximately ~45 sec)
-            if attempts % 10 == 0 {
+            // MI: stop resending once the confirmation timeout passed
+            if self
+                .confirm_timeout
+                .is_some_and(|timeout| started_at.elapsed().ge(&timeout))
+            {
+                return self
+                    .settle_unconfirmed(
+                        &progress_bar,
+                        &signatures,
+                        "Confirmation timeout",
+                        ClientError {
+                            request: None,
+                            kind: ClientErrorKind::Custom("Confirmation timeout".into()),
+                        },
+                    )
+                    .await;
+            }
+
+            // MI: cap the refreshes forced by an expired blockhash
+            if refresh_blockhash {
+                blockhash_refreshes += 1;
+                if blockhash_refreshes > BLOCKHASH_REFRESH_RETRIES {
+                    return self
+                        .settle_unconfirmed(
+                            &progress_bar,
+                            &signatures,
+                            "Max blockhash refreshes",
+                            ClientError {
+                                request: None,
+                                kind: ClientErrorKind::TransactionError(
+                                    TransactionError::BlockhashNotFound,
+                                ),
+                            },
+                        )
+                        .await;
+                }
+            }
+
+            // Sign tx with a new blockhash (after approximately ~45 sec, or once expired)
+            if attempts % 10 == 0 || refresh_blockhash || escalate {
                 // Reset the compute unit price
-                if self.dynamic_fee {
-                    let fee = match self.dynamic_fee().await {
-                        Ok(fee) => {
-                            let mut prio_fee = fee;
+                if self.dynamic_fee && attempts % 10 == 0 {
+                    let fee_started_at = Instant::now();
+                    let fee = match self.dynamic_fee_with_cache().await {
+                        Ok((estimate, provider, cached)) => {
+                            let mut prio_fee = self.cap_dynamic_fee(estimate);
+                            let mut requested = estimate;
                             // MI: calc uplimit of priority fee for precious fee difficulty, eg. diff > 27
-                            if let Some(DifficultyPayload {
-                                solution_difficulty,
-                                extra_fee_difficulty,
-                                extra_fee_percent,
-                                ..
-                            }) = difficulty_payload
-                            {
-                                if solution_difficulty > extra_fee_difficulty {
-                                    prio_fee = if let Some(priority_fee_cap) = self.priority_fee_cap
-                                    {
-                                        priority_fee_cap.min(
-                                            prio_fee
-                                                .saturating_mul(
-                                                    100u64.saturating_add(extra_fee_percent),
-                                                )
-                                                .saturating_div(100),
-                                        )
-                                    } else {
-                                        // MI: not exceed 300K
-                                        300_000.min(
-                                            prio_fee
-                                                .saturating_mul(
-                                                    100u64.saturating_add(extra_fee_percent),
-                                                )
-                                                .saturating_div(100),
-                                        )
-                                    }
-                                }
+                            if let Some(difficulty_payload) = difficulty_payload.as_ref() {
+                                prio_fee = extra_difficulty_fee(
+                                    prio_fee,
+                                    difficulty_payload,
+                                    self.priority_fee_cap,
+                                );
+                                requested = extra_difficulty_fee(
+                                    estimate,
+                                    difficulty_payload,
+                                    Some(u64::MAX),
+                                );
+                            }
+                            // MI: the estimate was clamped, the fee paid is less than it suggested
+                            if exceeds_cap(requested, self.priority_fee_cap) {
+                                *self.last_fee_capped.write().unwrap() = true;
                             }
-                            progress_bar
-                                .println(format!("  Priority fee: {} microlamports", prio_fee));
+                            prio_fee = clamp_priority_fee(prio_fee, max_priority_fee);
+                            let mode = if self.priority_fee_auto {
+                                "dynamic, "
+                            } else {
+                                ""
+                            };
+                            let cached = if cached && self.verbose {
+                                ", cached"
+                            } else {
+                                ""
+                            };
+                            progress_bar.println(format!(
+                                "  Priority fee: {} microlamports ({}{}{})",
+                                prio_fee, mode, provider, cached
+                            ));
                             prio_fee
                         }
+                        // MI: in auto mode the static fee is expected whenever estimates fail
+                        Err(err) if self.priority_fee_auto => {
+                            let fee = clamp_priority_fee(
+                                self.priority_fee.unwrap_or(0),
+                                max_priority_fee,
+                            );
+                            progress_bar.println(format!(
+                                "  Priority fee: {} microlamports (static, dynamic fee unavailable)",
+                                fee
+                            ));
+                            if self.verbose {
+                                progress_bar.println(format!("  {}", redact_urls(&err)));
+                            }
+                            fee
+                        }
                         Err(err) => {
-                            let fee = self.priority_fee.unwrap_or(0);
+                            let fee = clamp_priority_fee(
+                                self.priority_fee.unwrap_or(0),
+                                max_priority_fee,
+                            );
                             log_warning(
                                 &progress_bar,
                                 &format!(
@@ -176,15 +333,45 @@ impl Miner {
                             fee
                         }
                     };
+                    self.last_send_timings.write().unwrap().fee_estimate +=
+                        fee_started_at.elapsed();
 
+                    cu_price = fee;
+                    base_cu_price = fee;
                     final_ixs.remove(1);
                     final_ixs.insert(1, ComputeBudgetInstruction::set_compute_unit_price(fee));
                     tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
                 }
 
+                // MI: raise the fee on top of the base fee for each retry that did not land
+                if fee_escalation.gt(&0) {
+                    let requested = base_cu_price.saturating_add(fee_escalation);
+                    let escalated = requested.min(escalation_cap);
+                    if escalate {
+                        progress_bar.println(format!(
+                            "  Escalated priority fee: {} microlamports (+{})",
+                            escalated,
+                            escalated.saturating_sub(base_cu_price)
+                        ));
+                    }
+                    if exceeds_cap(requested, Some(escalation_cap)) {
+                        *self.last_fee_capped.write().unwrap() = true;
+                    }
+                    cu_price = escalated;
+                    final_ixs.remove(1);
+                    final_ixs.insert(
+                        1,
+                        ComputeBudgetInstruction::set_compute_unit_price(cu_price),
+                    );
+                    tx = Transaction::new_with_payer(&final_ixs, Some(&fee_payer.pubkey()));
+                }
+                escalate = false;
+
                 // Resign the tx
                 // MI: next line was born in ore-cli 2.2.1, later than loop section below
                 let (hash, _slot) = get_latest_blockhash_with_retries(&client).await?;
+                blockhash = hash;
+                refresh_blockhash = false;
 
                 // // MI: use loop to retry, otherwise program stops when .await.unwrap() failure
                 // let (hash, _slot) = loop {
@@ -201,15 +388,59 @@ impl Miner {
                 } else {
                     tx.sign(&[&signer, &fee_payer], hash);
                 }
+                if let Some(table) = lookup_table.as_ref() {
+                    match versioned_transaction(&final_ixs, table, &signer, &fee_payer, hash) {
+                        Ok(signed) => versioned_tx = Some(signed),
+                        Err(err) => {
+                            log_warning(
+                                &progress_bar,
+                                &format!(
+                                    "Failed to compile with the lookup table: {}. Sending a legacy transaction.",
+                                    err
+                                ),
+                            );
+                            lookup_table = None;
+                            versioned_tx = None;
+                        }
+                    }
+                }
+                progress_bar.println(format!(
+                    "  Total fee: {} SOL",
+                    lamports_to_sol(transaction_fee(num_signatures, cu_limit, cu_price))
+                ));
+                if self.verbose {
+                    log_instructions(&progress_bar, &final_ixs, cu_limit, cu_price);
+                    if let Some(table) = lookup_table.as_ref() {
+                        progress_bar.println(format!(
+                            "  Versioned transaction with lookup table {}",
+                            table.key
+                        ));
+                    }
+                }
             }
 
             // Send transaction
             attempts += 1;
-            match send_client
-                .send_transaction_with_config(&tx, send_cfg)
-                .await
-            {
-                Ok(sig) => {
+            let submit_started_at = Instant::now();
+            let result = match versioned_tx.as_ref() {
+                Some(versioned_tx) => {
+                    self.send_transaction_broadcast(versioned_tx, send_cfg)
+                        .await
+                }
+                None => self.send_transaction_broadcast(&tx, send_cfg).await,
+            };
+            self.last_send_timings.write().unwrap().submit += submit_started_at.elapsed();
+            match result {
+                Ok((sig, accepted_by)) => {
+                    // MI: name the --broadcast-rpc that beat the primary, once per transaction
+                    if let Some(url) = accepted_by.filter(|_| signatures.is_empty()) {
+                        progress_bar.println(format!("  Accepted first by {}", redact_url(&url)));
+                    }
+                    if !signatures.contains(&sig) {
+                        signatures.push(sig);
+                        cu_prices.push(cu_price);
+                    }
+
                     // Skip confirmation
                     if skip_confirm {
                         progress_bar.finish_with_message(format!("Sent: {}", sig));
@@ -217,130 +448,349 @@ impl Miner {
                     }
 
                     // Confirm transaction
-                    'confirm: for _ in 0..CONFIRM_RETRIES {
+                    for _ in 0..confirm_retries(self.commitment) {
                         tokio::time::sleep(Duration::from_millis(CONFIRM_DELAY)).await;
-                        match client.get_signature_statuses(&[sig]).await {
+
+                        // MI: stop waiting once a better hash is found, the caller resubmits it
+                        if let Some(DifficultyPayload {
+                            solution_difficulty,
+                            replace_if_improves: Some((risk_window, margin)),
+                            ..
+                        }) = difficulty_payload.as_ref()
+                        {
+                            if let Some((difficulty, _)) =
+                                risk_window.improved(*solution_difficulty, *margin)
+                            {
+                                progress_bar.finish_with_message(format!(
+                                    "{} Found difficulty {} before confirmation",
+                                    "REPLACE".bold().yellow(),
+                                    difficulty
+                                ));
+                                return Err(SendError::Replaced.into());
+                            }
+                        }
+
+                        // MI: an earlier, cheaper copy can still land after an escalation
+                        // re-signed the tx, so every copy sent is polled
+                        match client.get_signature_statuses(&signatures).await {
                             Ok(signature_statuses) => {
-                                for status in signature_statuses.value {
-                                    if let Some(status) = status {
-                                        if let Some(err) = status.err {
-                                            match err {
-                                                // Instruction error
-                                                solana_sdk::transaction::TransactionError::InstructionError(_, err) => {
-                                                    match err {
-                                                        // Custom instruction error, parse into OreError
-                                                        solana_program::instruction::InstructionError::Custom(err_code) => {
-                                                            match err_code {
-                                                                e if e == OreError::NeedsReset as u32 => {
-                                                                    attempts = 0;
-                                                                    log_error(&progress_bar, "Needs reset. Retrying...", false);
-                                                                    break 'confirm;
-                                                                },
-                                                                _ => {
-                                                                    log_error(&progress_bar, &err.to_string(), true);
-                                                                    return Err(ClientError {
-                                                                        request: None,
-                                                                        kind: ClientErrorKind::Custom(err.to_string()),
-                                                                    });
-                                                                }
-                                                            }
-                                                        },
+                                let mut landing = false;
+                                let mut failure = None;
+                                for ((sent_sig, sent_cu_price), status) in signatures
+                                    .iter()
+                                    .zip(cu_prices.iter())
+                                    .zip(signature_statuses.value)
+                                {
+                                    let Some(status) = status else {
+                                        continue;
+                                    };
+                                    match (status.err, status.confirmation_status) {
+                                        // Only the newest copy's error ends the wait, an older
+                                        // copy may fail once the newest one lands
+                                        (Some(err), _) => {
+                                            if sent_sig.eq(&sig) {
+                                                failure = Some(err);
+                                            }
+                                        }
+                                        // MI: confirm once the status reaches the --commitment target
+                                        (None, Some(confirmation))
+                                            if reaches_commitment(
+                                                &confirmation,
+                                                self.commitment,
+                                            ) =>
+                                        {
+                                            let now = Local::now();
+                                            let formatted_time =
+                                                now.format("%Y-%m-%d %H:%M:%S").to_string();
+                                            progress_bar.println(format!(
+                                                "  Timestamp: {}",
+                                                formatted_time
+                                            ));
+                                            if sent_sig.ne(&sig) {
+                                                progress_bar.println(format!(
+                                                    "  An earlier copy landed at {} microlamports",
+                                                    sent_cu_price
+                                                ));
+                                            }
+                                            progress_bar.finish_with_message(format!(
+                                                "{} {}",
+                                                "OK".bold().green(),
+                                                sent_sig
+                                            ));
+                                            *self.last_fee_lamports.write().unwrap() =
+                                                transaction_fee(
+                                                    tx.signatures.len(),
+                                                    cu_limit,
+                                                    *sent_cu_price,
+                                                );
+                                            return Ok(*sent_sig);
+                                        }
+                                        (None, _) => landing = true,
+                                    }
+                                }
 
-                                                        // Non custom instruction error, return
+                                // Wait for a copy that is landing to settle before failing
+                                if let Some(err) = failure.filter(|_| !landing) {
+                                    match err {
+                                        // Instruction error
+                                        TransactionError::InstructionError(ix_index, err) => {
+                                            match err {
+                                                // Custom instruction error, parse into OreError
+                                                InstructionError::Custom(err_code) => {
+                                                    match err_code {
+                                                        // MI: keep the error code so the caller can bundle a reset and retry
+                                                        e if e == OreError::NeedsReset as u32 => {
+                                                            log_error(
+                                                                &progress_bar,
+                                                                "Needs reset",
+                                                                true,
+                                                                self.verbose,
+                                                            );
+                                                            return Err(ClientError {
+                                                                request: None,
+                                                                kind: ClientErrorKind::TransactionError(
+                                                                    TransactionError::InstructionError(ix_index, err),
+                                                                ),
+                                                            });
+                                                        }
+                                                        // MI: name the ORE program errors, other programs keep the raw code
                                                         _ => {
-                                                            log_error(&progress_bar, &err.to_string(), true);
+                                                            let is_ore = final_ixs
+                                                                .get(ix_index as usize)
+                                                                .is_some_and(|ix| {
+                                                                    ix.program_id.eq(&ore_api::ID)
+                                                                });
+                                                            let msg = match program_error_message(
+                                                                err_code,
+                                                            ) {
+                                                                Some(msg)
+                                                                    if is_ore && self.verbose =>
+                                                                {
+                                                                    format!("{} ({})", msg, err)
+                                                                }
+                                                                Some(msg) if is_ore => {
+                                                                    msg.to_string()
+                                                                }
+                                                                _ => err.to_string(),
+                                                            };
+                                                            log_error(
+                                                                &progress_bar,
+                                                                &msg,
+                                                                true,
+                                                                self.verbose,
+                                                            );
                                                             return Err(ClientError {
                                                                 request: None,
-                                                                kind: ClientErrorKind::Custom(err.to_string()),
+                                                                kind: ClientErrorKind::Custom(msg),
                                                             });
                                                         }
                                                     }
-                                                },
+                                                }
 
-                                                // Non instruction error, return
+                                                // Non custom instruction error, return
                                                 _ => {
-                                                    log_error(&progress_bar, &err.to_string(), true);
+                                                    log_error(
+                                                        &progress_bar,
+                                                        &err.to_string(),
+                                                        true,
+                                                        self.verbose,
+                                                    );
                                                     return Err(ClientError {
                                                         request: None,
-                                                        kind: ClientErrorKind::Custom(err.to_string()),
+                                                        kind: ClientErrorKind::Custom(
+                                                            err.to_string(),
+                                                        ),
                                                     });
                                                 }
                                             }
-                                        } else if let Some(confirmation) =
-                                            status.confirmation_status
-                                        {
-                                            match confirmation {
-                                                TransactionConfirmationStatus::Processed => {}
-                                                TransactionConfirmationStatus::Confirmed
-                                                | TransactionConfirmationStatus::Finalized => {
-                                                    let now = Local::now();
-                                                    let formatted_time =
-                                                        now.format("%Y-%m-%d %H:%M:%S").to_string();
-                                                    progress_bar.println(format!(
-                                                        "  Timestamp: {}",
-                                                        formatted_time
-                                                    ));
-                                                    progress_bar.finish_with_message(format!(
-                                                        "{} {}",
-                                                        "OK".bold().green(),
-                                                        sig
-                                                    ));
-                                                    return Ok(sig);
-                                                }
-                                            }
-                                        } else {
-                                            // MI
-                                            println!("No confirmation status available yet for current signature status.");
                                         }
-                                    } else {
-                                        // MI
-                                        println!("No status available yet for current signature.");
+
+                                        // Non instruction error, return
+                                        _ => {
+                                            log_error(
+                                                &progress_bar,
+                                                &err.to_string(),
+                                                true,
+                                                self.verbose,
+                                            );
+                                            return Err(ClientError {
+                                                request: None,
+                                                kind: ClientErrorKind::Custom(err.to_string()),
+                                            });
+                                        }
                                     }
                                 }
                             }
 
                             // Handle confirmation errors
                             Err(err) => {
-                                log_error(&progress_bar, &err.kind().to_string(), false);
+                                log_error(
+                                    &progress_bar,
+                                    &err.kind().to_string(),
+                                    false,
+                                    self.verbose,
+                                );
                             }
                         }
                     }
+
+                    // Refresh the blockhash if it expired before the tx landed
+                    if let Ok(false) = client
+                        .is_blockhash_valid(&blockhash, client.commitment())
+                        .await
+                    {
+                        log_warning(&progress_bar, "Blockhash expired. Refreshing...");
+                        refresh_blockhash = true;
+                    }
+
+                    // MI: it did not land in time, retry with a higher fee
+                    if let Some(step) = self.priority_fee_escalate {
+                        if step.gt(&0) && cu_price.lt(&escalation_cap) {
+                            fee_escalation = fee_escalation.saturating_add(step);
+                            escalate = true;
+                        }
+                    }
                 }
 
                 // Handle submit errors
                 Err(err) => {
-                    log_error(&progress_bar, &err.kind().to_string(), false);
+                    log_error(&progress_bar, &err.kind().to_string(), false, self.verbose);
+                    if is_blockhash_expired(&err) {
+                        refresh_blockhash = true;
+                    }
                 }
             }
 
             // Retry
             tokio::time::sleep(Duration::from_millis(GATEWAY_DELAY)).await;
             if attempts > GATEWAY_RETRIES {
-                log_error(&progress_bar, "Max retries", true);
-                return Err(ClientError {
-                    request: None,
-                    kind: ClientErrorKind::Custom("Max retries".into()),
-                });
+                return self
+                    .settle_unconfirmed(
+                        &progress_bar,
+                        &signatures,
+                        "Max retries",
+                        ClientError {
+                            request: None,
+                            kind: ClientErrorKind::Custom("Max retries".into()),
+                        },
+                    )
+                    .await;
+            }
+        }
+    }
+
+    // MI: a transaction that was not confirmed in time can still land. Check the signatures
+    // sent a few more times before reporting the failure, and hand back the last one so the
+    // caller can tell whether it landed later.
+    async fn settle_unconfirmed(
+        &self,
+        progress_bar: &ProgressBar,
+        signatures: &[Signature],
+        msg: &str,
+        err: ClientError,
+    ) -> ClientResult<Signature> {
+        let Some(last_signature) = signatures.last().copied() else {
+            log_error(progress_bar, msg, true, self.verbose);
+            return Err(err);
+        };
+        progress_bar.set_message(format!("{}. Checking whether it landed...", msg));
+        for _ in 0..UNCONFIRMED_CHECKS {
+            if let Ok(statuses) = self.rpc_client.get_signature_statuses(signatures).await {
+                for (sig, status) in signatures.iter().zip(statuses.value) {
+                    let landed = status.is_some_and(|status| {
+                        status.err.is_none()
+                            && status
+                                .confirmation_status
+                                .as_ref()
+                                .is_some_and(|confirmation| {
+                                    reaches_commitment(confirmation, self.commitment)
+                                })
+                    });
+                    if landed {
+                        progress_bar.finish_with_message(format!(
+                            "{} {} (landed after {})",
+                            "OK".bold().green(),
+                            sig,
+                            msg.to_lowercase()
+                        ));
+                        return Ok(*sig);
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(UNCONFIRMED_CHECK_DELAY)).await;
+        }
+        log_error(progress_bar, msg, true, self.verbose);
+        Err(SendError::Unconfirmed(last_signature).into())
+    }
+
+    // MI: send the same signed tx to the primary and every --broadcast-rpc at once. The copies
+    // share one signature, so the network lands at most one of them. Also returns the url of
+    // the --broadcast-rpc that accepted it first, if it was not the primary.
+    async fn send_transaction_broadcast<T>(
+        &self,
+        tx: &T,
+        send_cfg: RpcSendTransactionConfig,
+    ) -> ClientResult<(Signature, Option<String>)>
+    where
+        T: SerializableTransaction + Clone + Send + Sync + 'static,
+    {
+        if self.broadcast_clients.is_empty() {
+            let sig = self
+                .rpc_client
+                .send_transaction_with_config(tx, send_cfg)
+                .await?;
+            return Ok((sig, None));
+        }
+        let mut sends: FuturesUnordered<_> = std::iter::once(self.rpc_client.clone())
+            .chain(self.broadcast_clients.iter().cloned())
+            .enumerate()
+            .map(|(i, client)| {
+                let tx = tx.clone();
+                async move {
+                    let result = client.send_transaction_with_config(&tx, send_cfg).await;
+                    (i, client.url(), result)
+                }
+            })
+            .collect();
+        let mut primary_err = None;
+        let mut broadcast_err = None;
+        while let Some((i, url, result)) = sends.next().await {
+            match result {
+                Ok(sig) => {
+                    // Let the remaining sends finish in the background
+                    tokio::spawn(async move { while sends.next().await.is_some() {} });
+                    return Ok((sig, Some(url).filter(|_| i.gt(&0))));
+                }
+                Err(err) if i.eq(&0) => primary_err = Some(err),
+                Err(err) => broadcast_err = Some(err),
             }
         }
+        Err(primary_err
+            .or(broadcast_err)
+            .unwrap_or_else(|| ClientError {
+                request: None,
+                kind: ClientErrorKind::Custom("No RPC to send to".into()),
+            }))
     }
 
-    pub async fn check_balance(&self) {
+    // MI: a drained fee payer is returned as an error, so the miner can report why it stopped
+    pub async fn check_balance(&self) -> ClientResult<()> {
         // Throw error if balance is less than min
         if let Ok(balance) = self
             .rpc_client
-            .get_balance(&self.fee_payer().pubkey())
+            .get_balance(&self.fee_payer()?.pubkey())
             .await
         {
             if balance <= sol_to_lamports(MIN_SOL_BALANCE) {
-                panic!(
-                    "{} Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
-                    "ERROR".bold().red(),
+                return Err(SendError::InsufficientBalance(format!(
+                    "Insufficient balance: {} SOL\nPlease top up with at least {} SOL",
                     lamports_to_sol(balance),
                     MIN_SOL_BALANCE
-                );
+                ))
+                .into());
             }
         }
+        Ok(())
     }
 
     // TODO
@@ -401,7 +851,228 @@ impl Miner {
     }
 }
 
-fn log_error(progress_bar: &ProgressBar, err: &str, finish: bool) {
+pub fn clamp_priority_fee(fee: u64, max_priority_fee: Option<u64>) -> u64 {
+    match max_priority_fee {
+        Some(max_priority_fee) => fee.min(max_priority_fee),
+        None => fee,
+    }
+}
+
+// Translate a total fee budget into a compute unit price for the given compute budget
+pub fn max_cu_price(max_total_fee: u64, num_signatures: usize, cu_limit: u32) -> u64 {
+    max_total_fee
+        .saturating_sub((num_signatures as u64).saturating_mul(LAMPORTS_PER_SIGNATURE))
+        .saturating_mul(1_000_000)
+        .saturating_div((cu_limit as u64).max(1))
+}
+
+// MI: parse the program error code carried by a client error
+pub fn ore_error_code(err: &ClientError) -> Option<u32> {
+    match err.get_transaction_error() {
+        Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Some(code),
+        _ => None,
+    }
+}
+
+// MI: what the ORE program errors mean to a miner, keyed by the program's error enum
+const PROGRAM_ERRORS: &[(u32, &str)] = &[
+    (
+        OreError::NeedsReset as u32,
+        "Needs reset: the epoch ended before the transaction landed",
+    ),
+    (
+        OreError::HashInvalid as u32,
+        "Invalid hash: the solution does not match the current challenge",
+    ),
+    (
+        OreError::HashTooEasy as u32,
+        "Hash too easy: the solution is below the program min difficulty",
+    ),
+    (
+        OreError::ClaimTooLarge as u32,
+        "Claim too large: the amount exceeds the claimable rewards",
+    ),
+    (
+        OreError::ClockInvalid as u32,
+        "Invalid clock: the cluster clock is out of range",
+    ),
+    (
+        OreError::Spam as u32,
+        "Too soon: the proof was last submitted less than a minute ago",
+    ),
+    (
+        OreError::MaxSupply as u32,
+        "Max supply: the ORE supply cap has been reached",
+    ),
+    (
+        OreError::AuthFailed as u32,
+        "Authorization failed: the signer is not the proof authority",
+    ),
+];
+
+fn program_error_message(code: u32) -> Option<&'static str> {
+    PROGRAM_ERRORS
+        .iter()
+        .find(|(error_code, _)| error_code.eq(&code))
+        .map(|(_, msg)| *msg)
+}
+
+pub fn is_needs_reset(err: &ClientError) -> bool {
+    ore_error_code(err).eq(&Some(OreError::NeedsReset as u32))
+}
+
+// MI: the pending transaction was abandoned for a better solution, see --submit-only-if-improves
+pub fn is_replaced(err: &ClientError) -> bool {
+    matches!(send_error(err), Some(SendError::Replaced))
+}
+
+// MI: the last signature of a transaction that timed out unconfirmed, it may still land
+pub fn unconfirmed_signature(err: &ClientError) -> Option<Signature> {
+    match send_error(err)? {
+        SendError::Unconfirmed(sig) => Some(*sig),
+        SendError::Replaced | SendError::InsufficientBalance(_) => None,
+    }
+}
+
+// MI: raise the fee by --extra-fee-percent for a solution above --extra-fee-difficulty, up to
+// the priority fee cap
+fn extra_difficulty_fee(
+    fee: u64,
+    difficulty_payload: &DifficultyPayload,
+    priority_fee_cap: Option<u64>,
+) -> u64 {
+    if difficulty_payload
+        .solution_difficulty
+        .le(&difficulty_payload.extra_fee_difficulty)
+    {
+        return fee;
+    }
+    priority_fee_cap.unwrap_or(MAX_ESCALATED_PRIORITY_FEE).min(
+        fee.saturating_mul(100u64.saturating_add(difficulty_payload.extra_fee_percent))
+            .saturating_div(100),
+    )
+}
+
+// MI: a fee that only reaches the cap was not clamped
+fn exceeds_cap(requested: u64, cap: Option<u64>) -> bool {
+    cap.is_some_and(|cap| requested.gt(&cap))
+}
+
+// MI: polls of the confirmation loop, long enough for the target commitment to be reached
+fn confirm_retries(target: CommitmentLevel) -> usize {
+    match target {
+        CommitmentLevel::Finalized => FINALIZED_CONFIRM_RETRIES,
+        _ => CONFIRM_RETRIES,
+    }
+}
+
+// MI: whether a signature status satisfies the target commitment level
+fn reaches_commitment(status: &TransactionConfirmationStatus, target: CommitmentLevel) -> bool {
+    match status {
+        TransactionConfirmationStatus::Processed => {
+            matches!(target, CommitmentLevel::Processed)
+        }
+        TransactionConfirmationStatus::Confirmed => matches!(
+            target,
+            CommitmentLevel::Processed | CommitmentLevel::Confirmed
+        ),
+        TransactionConfirmationStatus::Finalized => true,
+    }
+}
+
+// MI: the fee payer cannot cover the fee or the rent of a new account
+pub fn is_insufficient_funds(err: &ClientError) -> bool {
+    if matches!(send_error(err), Some(SendError::InsufficientBalance(_))) {
+        return true;
+    }
+    if matches!(
+        err.get_transaction_error(),
+        Some(TransactionError::InsufficientFundsForFee)
+            | Some(TransactionError::InsufficientFundsForRent { .. })
+    ) {
+        return true;
+    }
+    let msg = err.to_string().to_lowercase();
+    [
+        "insufficient funds",
+        "insufficient balance",
+        "insufficient lamports",
+    ]
+    .iter()
+    .any(|pattern| msg.contains(pattern))
+}
+
+// MI: the instructions as a signed v0 transaction that looks up accounts in the table
+fn versioned_transaction(
+    ixs: &[Instruction],
+    lookup_table: &AddressLookupTableAccount,
+    signer: &Keypair,
+    fee_payer: &Keypair,
+    blockhash: Hash,
+) -> Result<VersionedTransaction, String> {
+    let message = v0::Message::try_compile(
+        &fee_payer.pubkey(),
+        ixs,
+        std::slice::from_ref(lookup_table),
+        blockhash,
+    )
+    .map_err(|err| err.to_string())?;
+    let signed = if signer.pubkey() == fee_payer.pubkey() {
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[signer])
+    } else {
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[signer, fee_payer])
+    };
+    signed.map_err(|err| err.to_string())
+}
+
+fn is_blockhash_expired(err: &ClientError) -> bool {
+    matches!(
+        err.get_transaction_error(),
+        Some(TransactionError::BlockhashNotFound)
+    ) || err.to_string().contains("Blockhash not found")
+}
+
+// Base fee per signature plus the prioritization fee for the requested compute units
+pub fn transaction_fee(num_signatures: usize, cu_limit: u32, cu_price: u64) -> u64 {
+    (num_signatures as u64)
+        .saturating_mul(LAMPORTS_PER_SIGNATURE)
+        .saturating_add(
+            (cu_limit as u64)
+                .saturating_mul(cu_price)
+                .saturating_div(1_000_000),
+        )
+}
+
+fn log_instructions(progress_bar: &ProgressBar, ixs: &[Instruction], cu_limit: u32, cu_price: u64) {
+    progress_bar.println(format!("  Compute budget: {} CUs", cu_limit));
+    progress_bar.println(format!("  Compute unit price: {} microlamports", cu_price));
+    for (i, ix) in ixs.iter().enumerate() {
+        progress_bar.println(format!(
+            "  Instruction {}: program {} ({} bytes of data)",
+            i,
+            ix.program_id,
+            ix.data.len()
+        ));
+        if ix.program_id.eq(&MEMO_PROGRAM_ID) {
+            progress_bar.println(format!("    Memo: {}", String::from_utf8_lossy(&ix.data)));
+        }
+        for account in ix.accounts.iter() {
+            progress_bar.println(format!(
+                "    {} {}{}",
+                account.pubkey,
+                if account.is_signer { "signer " } else { "" },
+                if account.is_writable {
+                    "writable"
+                } else {
+                    "readonly"
+                }
+            ));
+        }
+    }
+}
+
+fn log_error(progress_bar: &ProgressBar, err: &str, finish: bool, verbose: bool) {
+    let err = hints::with_hint(&redact_urls(err), verbose);
     if finish {
         progress_bar.finish_with_message(format!("{} {}", "ERROR".bold().red(), err));
     } else {
@@ -412,3 +1083,379 @@ fn log_error(progress_bar: &ProgressBar, err: &str, finish: bool) {
 fn log_warning(progress_bar: &ProgressBar, msg: &str) {
     progress_bar.println(format!("  {} {}", "WARNING".bold().yellow(), msg));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ore_api::consts::MINT_ADDRESS;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_program::{pubkey::Pubkey, system_instruction};
+    use solana_sdk::commitment_config::CommitmentConfig;
+
+    use super::*;
+    use crate::{
+        rpc_sender::mock::{keypair_file, signature_status, with_context, Calls, ScriptedSender},
+        transfer::transfer_ixs,
+        Error,
+    };
+
+    fn test_miner(sender: ScriptedSender, commitment: CommitmentLevel) -> (Miner, Calls) {
+        let (client, calls) = sender.client(CommitmentConfig { commitment });
+        let keypair_filepath = keypair_file(&Keypair::new());
+        let miner = Miner::builder(Arc::new(client))
+            .keypair_filepath(Some(keypair_filepath.clone()))
+            .fee_payer_filepath(Some(keypair_filepath))
+            .commitment(commitment)
+            .build();
+        (miner, calls)
+    }
+
+    fn transfer_ix(miner: &Miner) -> Instruction {
+        let pubkey = miner.signer().unwrap().pubkey();
+        system_instruction::transfer(&pubkey, &pubkey, 1)
+    }
+
+    #[tokio::test]
+    async fn confirms_once_the_status_reaches_the_commitment() {
+        let sender = ScriptedSender::new()
+            .funded()
+            .reply(RpcRequest::GetSignatureStatuses, Ok(signature_status(None)))
+            .reply(
+                RpcRequest::GetSignatureStatuses,
+                Ok(signature_status(Some("processed"))),
+            )
+            .reply(
+                RpcRequest::GetSignatureStatuses,
+                Ok(signature_status(Some("confirmed"))),
+            );
+        let (miner, calls) = test_miner(sender, CommitmentLevel::Confirmed);
+        let ix = transfer_ix(&miner);
+        let result = miner
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false, None)
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(calls.count(RpcRequest::SendTransaction), 1);
+        assert_eq!(calls.count(RpcRequest::GetSignatureStatuses), 3);
+    }
+
+    #[tokio::test]
+    async fn processed_commitment_confirms_on_processed() {
+        let sender = ScriptedSender::new()
+            .funded()
+            .reply(RpcRequest::GetSignatureStatuses, Ok(signature_status(None)))
+            .reply(
+                RpcRequest::GetSignatureStatuses,
+                Ok(signature_status(Some("processed"))),
+            );
+        let (miner, calls) = test_miner(sender, CommitmentLevel::Processed);
+        let ix = transfer_ix(&miner);
+        let result = miner
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false, None)
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(calls.count(RpcRequest::GetSignatureStatuses), 2);
+    }
+
+    #[tokio::test]
+    async fn refreshes_the_blockhash_after_expiry() {
+        let sender = ScriptedSender::new().funded().reply(
+            RpcRequest::SendTransaction,
+            Err(ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound).into()),
+        );
+        let (miner, calls) = test_miner(sender, CommitmentLevel::Confirmed);
+        let ix = transfer_ix(&miner);
+        let result = miner
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false, None)
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(calls.count(RpcRequest::SendTransaction), 2);
+        assert_eq!(calls.count(RpcRequest::GetLatestBlockhash), 2);
+    }
+
+    #[tokio::test]
+    async fn counts_a_late_landing_after_the_timeout_as_success() {
+        // Unknown for the whole confirmation loop and the first check after the timeout
+        let mut sender = ScriptedSender::new().funded();
+        for _ in 0..CONFIRM_RETRIES + 1 {
+            sender = sender.reply(RpcRequest::GetSignatureStatuses, Ok(signature_status(None)));
+        }
+        let (client, calls) = sender.client(CommitmentConfig::confirmed());
+        let keypair_filepath = keypair_file(&Keypair::new());
+        let miner = Miner::builder(Arc::new(client))
+            .keypair_filepath(Some(keypair_filepath.clone()))
+            .fee_payer_filepath(Some(keypair_filepath))
+            .confirm_timeout(Some(Duration::from_secs(1)))
+            .build();
+        let ix = transfer_ix(&miner);
+        let result = miner
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false, None)
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(calls.count(RpcRequest::SendTransaction), 1);
+        assert_eq!(
+            calls.count(RpcRequest::GetSignatureStatuses),
+            CONFIRM_RETRIES + 2
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_the_unconfirmed_signature() {
+        let mut sender = ScriptedSender::new();
+        for _ in 0..UNCONFIRMED_CHECKS {
+            sender = sender.reply(RpcRequest::GetSignatureStatuses, Ok(signature_status(None)));
+        }
+        let (miner, _) = test_miner(sender, CommitmentLevel::Confirmed);
+        let sig = Signature::new_unique();
+        let err = miner
+            .settle_unconfirmed(
+                &ProgressBar::hidden(),
+                &[sig],
+                "Confirmation timeout",
+                ClientErrorKind::Custom("Confirmation timeout".into()).into(),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(unconfirmed_signature(&err), Some(sig));
+        assert!(!is_replaced(&err));
+    }
+
+    // Sends the instructions with a fee payer other than the signer, returns the submitted tx
+    async fn send_with_fee_payer(
+        signer: &Keypair,
+        fee_payer: &Keypair,
+        ixs: &[Instruction],
+    ) -> Transaction {
+        let (client, calls) = ScriptedSender::new()
+            .funded()
+            .client(CommitmentConfig::confirmed());
+        let miner = Miner::builder(Arc::new(client))
+            .keypair_filepath(Some(keypair_file(signer)))
+            .fee_payer_filepath(Some(keypair_file(fee_payer)))
+            .build();
+        let result = miner
+            .send_and_confirm(ixs, ComputeBudget::Fixed(200_000), false, None)
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        let mut transactions = calls.transactions();
+        assert_eq!(transactions.len(), 1);
+        transactions.remove(0)
+    }
+
+    // The fee payer is the payer account, the signer only co-signs
+    fn assert_paid_by(tx: &Transaction, fee_payer: &Keypair, signer: &Keypair) {
+        assert_eq!(tx.message.account_keys[0], fee_payer.pubkey());
+        assert_eq!(
+            tx.message.signer_keys(),
+            vec![&fee_payer.pubkey(), &signer.pubkey()]
+        );
+        assert!(tx.verify().is_ok());
+    }
+
+    // The accounts that sign the first instruction calling the program, each listed once
+    fn instruction_signers(tx: &Transaction, program_id: &Pubkey) -> Vec<Pubkey> {
+        let message = &tx.message;
+        let ix = message
+            .instructions
+            .iter()
+            .find(|ix| message.account_keys[ix.program_id_index as usize].eq(program_id))
+            .expect("instruction not found");
+        let mut signers: Vec<Pubkey> = vec![];
+        for index in ix.accounts.iter().map(|index| *index as usize) {
+            let key = message.account_keys[index];
+            if message.is_signer(index) && !signers.contains(&key) {
+                signers.push(key);
+            }
+        }
+        signers
+    }
+
+    #[tokio::test]
+    async fn stake_is_paid_by_the_fee_payer_and_signed_by_the_authority() {
+        let (signer, fee_payer) = (Keypair::new(), Keypair::new());
+        let sender = spl_associated_token_account::get_associated_token_address(
+            &signer.pubkey(),
+            &MINT_ADDRESS,
+        );
+        let ix = ore_api::instruction::stake(signer.pubkey(), sender, 1);
+        let tx = send_with_fee_payer(&signer, &fee_payer, &[ix]).await;
+        assert_paid_by(&tx, &fee_payer, &signer);
+        assert_eq!(
+            instruction_signers(&tx, &ore_api::ID),
+            vec![signer.pubkey()]
+        );
+    }
+
+    #[tokio::test]
+    async fn claim_is_paid_by_the_fee_payer_and_signed_by_the_authority() {
+        let (signer, fee_payer) = (Keypair::new(), Keypair::new());
+        let beneficiary = spl_associated_token_account::get_associated_token_address(
+            &signer.pubkey(),
+            &MINT_ADDRESS,
+        );
+        let ix = ore_api::instruction::claim(signer.pubkey(), beneficiary, 1);
+        let tx = send_with_fee_payer(&signer, &fee_payer, &[ix]).await;
+        assert_paid_by(&tx, &fee_payer, &signer);
+        assert_eq!(
+            instruction_signers(&tx, &ore_api::ID),
+            vec![signer.pubkey()]
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_is_paid_by_the_fee_payer_and_signed_by_the_authority() {
+        let (signer, fee_payer) = (Keypair::new(), Keypair::new());
+        let ixs = transfer_ixs(
+            signer.pubkey(),
+            fee_payer.pubkey(),
+            Pubkey::new_unique(),
+            1,
+            true,
+        );
+        let tx = send_with_fee_payer(&signer, &fee_payer, &ixs).await;
+        assert_paid_by(&tx, &fee_payer, &signer);
+        // The fee payer funds the recipient token account, the signer owns the tokens sent
+        assert_eq!(
+            instruction_signers(&tx, &spl_associated_token_account::id()),
+            vec![fee_payer.pubkey()]
+        );
+        assert_eq!(
+            instruction_signers(&tx, &spl_token::id()),
+            vec![signer.pubkey()]
+        );
+    }
+
+    fn instruction_error(err: InstructionError) -> ClientError {
+        ClientErrorKind::TransactionError(TransactionError::InstructionError(2, err)).into()
+    }
+
+    #[test]
+    fn classifies_the_needs_reset_error_by_code() {
+        let err = instruction_error(InstructionError::Custom(OreError::NeedsReset as u32));
+        assert_eq!(ore_error_code(&err), Some(OreError::NeedsReset as u32));
+        assert!(is_needs_reset(&err));
+
+        let err = instruction_error(InstructionError::Custom(OreError::HashInvalid as u32));
+        assert_eq!(ore_error_code(&err), Some(OreError::HashInvalid as u32));
+        assert!(!is_needs_reset(&err));
+    }
+
+    #[test]
+    fn ignores_errors_without_a_program_error_code() {
+        let errs: [ClientError; 4] = [
+            instruction_error(InstructionError::InvalidAccountData),
+            ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound).into(),
+            ClientErrorKind::Custom("custom program error: 0x0".into()).into(),
+            ClientErrorKind::Io(io::Error::other("timed out")).into(),
+        ];
+        for err in errs {
+            assert_eq!(ore_error_code(&err), None, "{}", err);
+            assert!(!is_needs_reset(&err), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_drained_fee_payer_is_an_insufficient_funds_error() {
+        let sender =
+            ScriptedSender::new().reply(RpcRequest::GetBalance, Ok(with_context(json!(0))));
+        let (miner, calls) = test_miner(sender, CommitmentLevel::Confirmed);
+        let ix = transfer_ix(&miner);
+        let err = miner
+            .send_and_confirm(&[ix], ComputeBudget::Fixed(200_000), false, None)
+            .await
+            .unwrap_err();
+        assert!(is_insufficient_funds(&err));
+        assert_eq!(calls.count(RpcRequest::SendTransaction), 0);
+        assert!(matches!(Error::from(err), Error::InsufficientFunds(_)));
+    }
+
+    #[test]
+    fn classifies_insufficient_funds() {
+        let insufficient: [ClientError; 3] = [
+            ClientErrorKind::TransactionError(TransactionError::InsufficientFundsForFee).into(),
+            ClientErrorKind::TransactionError(TransactionError::InsufficientFundsForRent {
+                account_index: 1,
+            })
+            .into(),
+            ClientErrorKind::Custom("Transfer: insufficient lamports 1000, need 890880".into())
+                .into(),
+        ];
+        for err in insufficient {
+            assert!(is_insufficient_funds(&err), "{}", err);
+        }
+
+        let err: ClientError =
+            ClientErrorKind::TransactionError(TransactionError::AccountNotFound).into();
+        assert!(!is_insufficient_funds(&err));
+    }
+
+    fn payload(solution_difficulty: u32, extra_fee_percent: u64) -> DifficultyPayload {
+        DifficultyPayload {
+            solution_difficulty,
+            expected_min_difficulty: 19,
+            extra_fee_difficulty: 27,
+            extra_fee_percent,
+            messaging_diff: 35,
+            max_priority_fee: None,
+            replace_if_improves: None,
+        }
+    }
+
+    #[test]
+    fn extra_fee_starts_above_the_extra_fee_difficulty() {
+        assert_eq!(extra_difficulty_fee(10_000, &payload(26, 50), None), 10_000);
+        assert_eq!(extra_difficulty_fee(10_000, &payload(27, 50), None), 10_000);
+        assert_eq!(extra_difficulty_fee(10_000, &payload(28, 50), None), 15_000);
+        assert_eq!(extra_difficulty_fee(10_000, &payload(28, 0), None), 10_000);
+        assert_eq!(
+            extra_difficulty_fee(10_000, &payload(28, 100), None),
+            20_000
+        );
+    }
+
+    #[test]
+    fn extra_fee_is_capped() {
+        assert_eq!(
+            extra_difficulty_fee(90_000, &payload(30, 50), Some(100_000)),
+            100_000
+        );
+        assert_eq!(
+            extra_difficulty_fee(60_000, &payload(30, 50), Some(100_000)),
+            90_000
+        );
+        // Without a cap the fee stops at the escalation ceiling
+        assert_eq!(
+            extra_difficulty_fee(250_000, &payload(30, 50), None),
+            MAX_ESCALATED_PRIORITY_FEE
+        );
+        assert_eq!(
+            extra_difficulty_fee(u64::MAX, &payload(30, 100), Some(100_000)),
+            100_000
+        );
+        // The cap applies to the extra fee only
+        assert_eq!(
+            extra_difficulty_fee(150_000, &payload(27, 50), Some(100_000)),
+            150_000
+        );
+    }
+
+    #[test]
+    fn only_a_fee_above_the_cap_counts_as_capped() {
+        assert!(!exceeds_cap(100_000, Some(100_000)));
+        assert!(exceeds_cap(100_001, Some(100_000)));
+        assert!(!exceeds_cap(u64::MAX, None));
+        // The uplift is compared uncapped, an estimate at the cap is raised above it
+        assert!(exceeds_cap(
+            extra_difficulty_fee(100_000, &payload(30, 50), Some(u64::MAX)),
+            Some(100_000)
+        ));
+    }
+
+    #[test]
+    fn finalized_commitment_waits_for_finalization() {
+        let confirm_window = |target| confirm_retries(target) as u64 * CONFIRM_DELAY;
+        assert_eq!(confirm_window(CommitmentLevel::Confirmed), 4_000);
+        assert!(confirm_window(CommitmentLevel::Finalized).ge(&13_000));
+    }
+}
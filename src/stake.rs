@@ -1,21 +1,62 @@
 use std::str::FromStr;
 
+use colored::*;
+use solana_client::rpc_request::TokenAccountsFilter;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 
 use crate::{
-    args::StakeArgs, cu_limits::CU_LIMIT_CLAIM, send_and_confirm::ComputeBudget,
-    utils::amount_f64_to_u64, Miner,
+    args::StakeArgs,
+    cu_limits::CU_LIMIT_CLAIM,
+    mine::{calculate_multiplier, stake_for_multiplier},
+    send_and_confirm::ComputeBudget,
+    utils::{amount_f64_to_u64, amount_u64_to_string, get_config, require_proof},
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn stake(&self, args: StakeArgs) {
+    pub async fn stake(&self, args: StakeArgs) -> Result<(), Error> {
+        self.stake_amount(args).await.map(|_| ())
+    }
+
+    // MI: stakes and returns the amount that was sent, 0 when no transaction was needed
+    pub(crate) async fn stake_amount(&self, args: StakeArgs) -> Result<u64, Error> {
         // Get signer
-        let signer = self.signer();
-        let sender = match args.token_account {
-            Some(address) => {
-                Pubkey::from_str(&address).expect("Failed to parse token account address")
-            }
+        self.check_signer_is_authority()?;
+        let signer = self.signer()?;
+        if args.list_token_accounts {
+            return self
+                .list_ore_token_accounts(signer.pubkey())
+                .await
+                .map(|_| 0);
+        }
+        let proof = require_proof(&self.rpc_client, signer.pubkey()).await?;
+
+        // MI: the program pays mining rewards into the proof balance, which is the stake itself.
+        // Claimable rewards already count toward the multiplier, so there is nothing to send.
+        if args.from_rewards {
+            let config = get_config(&self.rpc_client).await;
+            println!(
+                "{} Rewards are already staked in the proof, no transaction needed.",
+                "OK".bold().green()
+            );
+            println!("Stake: {} ORE", amount_u64_to_string(proof.balance));
+            println!(
+                "Multiplier: {:12}x",
+                calculate_multiplier(proof.balance, config.top_balance)
+            );
+            return Ok(0);
+        }
+        let sender = match args.token_account.as_ref() {
+            Some(address) => match Pubkey::from_str(address) {
+                Ok(address) => address,
+                Err(_) => {
+                    return Err(Error::Failed(format!(
+                        "Invalid token account address: {}",
+                        address
+                    )));
+                }
+            },
             None => spl_associated_token_account::get_associated_token_address(
                 &signer.pubkey(),
                 &ore_api::consts::MINT_ADDRESS,
@@ -23,23 +64,139 @@ impl Miner {
         };
 
         // Get token account
-        let Ok(Some(token_account)) = self.rpc_client.get_token_account(&sender).await else {
-            println!("Failed to fetch token account");
-            return;
+        let token_account = match self.rpc_client.get_token_account(&sender).await {
+            Ok(Some(token_account)) => token_account,
+            Ok(None) if args.token_account.is_some() => {
+                return Err(Error::Failed(format!(
+                    "Token account {} does not exist",
+                    sender
+                )));
+            }
+            Ok(None) => {
+                return Err(Error::Failed(format!(
+                    "The signer has no ORE token account at {}. List the others with `ore stake --list-token-accounts`.",
+                    sender
+                )));
+            }
+            Err(err) => {
+                return Err(Error::Failed(format!(
+                    "Failed to fetch token account {}: {}",
+                    sender, err
+                )));
+            }
         };
 
+        // MI: the program rejects any other account, catch it before paying the fee
+        if token_account
+            .mint
+            .ne(&ore_api::consts::MINT_ADDRESS.to_string())
+        {
+            return Err(Error::Failed(format!(
+                "Token account {} holds {}, not ORE",
+                sender, token_account.mint
+            )));
+        }
+        if token_account.owner.ne(&signer.pubkey().to_string()) {
+            return Err(Error::Failed(format!(
+                "Token account {} is owned by {}, not the signer {}",
+                sender,
+                token_account.owner,
+                signer.pubkey()
+            )));
+        }
+        let balance = u64::from_str(token_account.token_amount.amount.as_str())
+            .map_err(|err| Error::Failed(format!("Failed to parse token balance: {}", err)))?;
+
         // Parse amount
-        let amount: u64 = if let Some(amount) = args.amount {
+        let mut amount: u64 = if let Some(amount) = args.amount {
             amount_f64_to_u64(amount)
         } else {
-            u64::from_str(token_account.token_amount.amount.as_str())
-                .expect("Failed to parse token balance")
+            balance
         };
+        if amount.gt(&balance) {
+            return Err(Error::Failed(format!(
+                "Token account {} holds {} ORE, less than the {} ORE requested",
+                sender,
+                amount_u64_to_string(balance),
+                amount_u64_to_string(amount)
+            )));
+        }
+
+        // MI: stake only what the target multiplier needs, the top balance moves so recompute it
+        if let Some(target_multiplier) = args.target_multiplier {
+            let config = get_config(&self.rpc_client).await;
+            let needed = stake_for_multiplier(target_multiplier, config.top_balance)
+                .saturating_sub(proof.balance);
+            if needed.eq(&0) {
+                println!(
+                    "{} Multiplier {:.4}x already reaches the target {:.4}x",
+                    "OK".bold().green(),
+                    calculate_multiplier(proof.balance, config.top_balance),
+                    target_multiplier
+                );
+                return Ok(0);
+            }
+            println!(
+                "{} ORE needed to reach a {:.4}x multiplier",
+                amount_u64_to_string(needed),
+                target_multiplier
+            );
+            if needed.gt(&amount) {
+                println!(
+                    "{} Only {} ORE available to stake",
+                    "WARNING".bold().yellow(),
+                    amount_u64_to_string(amount)
+                );
+            }
+            amount = needed.min(amount);
+            if amount.eq(&0) {
+                return Ok(0);
+            }
+        }
 
         // Send tx
         let ix = ore_api::instruction::stake(signer.pubkey(), sender, amount);
         self.send_and_confirm(&[ix], ComputeBudget::Fixed(CU_LIMIT_CLAIM), false, None)
+            .await?;
+        Ok(amount)
+    }
+
+    // MI: ORE token accounts owned by the signer, the candidates for --token-account
+    async fn list_ore_token_accounts(&self, owner: Pubkey) -> Result<(), Error> {
+        let accounts = self
+            .rpc_client
+            .get_token_accounts_by_owner(
+                &owner,
+                TokenAccountsFilter::Mint(ore_api::consts::MINT_ADDRESS),
+            )
             .await
-            .ok();
+            .map_err(|err| Error::Failed(format!("Failed to fetch token accounts: {}", err)))?;
+        if accounts.is_empty() {
+            println!("No ORE token accounts found for {}", owner);
+            return Ok(());
+        }
+        let ata = spl_associated_token_account::get_associated_token_address(
+            &owner,
+            &ore_api::consts::MINT_ADDRESS,
+        )
+        .to_string();
+
+        // The RPC returns jsonParsed token accounts
+        for keyed in accounts {
+            let amount = serde_json::to_value(&keyed.account.data)
+                .ok()
+                .and_then(|data| {
+                    data["parsed"]["info"]["tokenAmount"]["uiAmountString"]
+                        .as_str()
+                        .map(str::to_string)
+                })
+                .unwrap_or_default();
+            if keyed.pubkey.eq(&ata) {
+                println!("{} {} ORE (associated token account)", keyed.pubkey, amount);
+            } else {
+                println!("{} {} ORE", keyed.pubkey, amount);
+            }
+        }
+        Ok(())
     }
 }
@@ -2,45 +2,38 @@ use std::str::FromStr;
 
 use colored::*;
 use ore_api::consts::MINT_ADDRESS;
-use solana_program::pubkey::Pubkey;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
 use solana_sdk::signature::Signer;
-use spl_token::amount_to_ui_amount;
 
 use crate::{
     args::TransferArgs,
     cu_limits::CU_LIMIT_CLAIM,
     send_and_confirm::ComputeBudget,
-    utils::{amount_f64_to_u64, ask_confirm},
-    Miner,
+    utils::{amount_f64_to_u64, amount_u64_to_string, ask_confirm, confirm_recipient, memo_line},
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn transfer(&self, args: TransferArgs) {
-        let signer = self.signer();
-        let pubkey = signer.pubkey();
-        let sender_tokens =
-            spl_associated_token_account::get_associated_token_address(&pubkey, &MINT_ADDRESS);
-        let mut ixs = vec![];
-
+    pub async fn transfer(&self, args: TransferArgs) -> Result<(), Error> {
+        // MI: offline transfers are built for the --authority wallet, which also pays the fees
+        let (pubkey, fee_payer) = if self.offline.is_some() {
+            (self.authority()?, self.authority()?)
+        } else {
+            (self.signer()?.pubkey(), self.fee_payer()?.pubkey())
+        };
         // Initialize recipient, if needed
-        let to = Pubkey::from_str(&args.to).expect("Failed to parse recipient wallet address");
+        let to = Pubkey::from_str(&args.to)
+            .map_err(|_| Error::Failed(format!("Invalid recipient address: {}", args.to)))?;
+        if !confirm_recipient(&to, &pubkey, args.yes) {
+            return Ok(());
+        }
         let recipient_tokens =
             spl_associated_token_account::get_associated_token_address(&to, &MINT_ADDRESS);
-        if self
+        let create_recipient = self
             .rpc_client
             .get_token_account(&recipient_tokens)
             .await
-            .is_err()
-        {
-            ixs.push(
-                spl_associated_token_account::instruction::create_associated_token_account(
-                    &signer.pubkey(),
-                    &to,
-                    &ore_api::consts::MINT_ADDRESS,
-                    &spl_token::id(),
-                ),
-            );
-        }
+            .is_err();
 
         // Parse amount to claim
         let amount = amount_f64_to_u64(args.amount);
@@ -48,32 +41,62 @@ impl Miner {
         // Confirm user wants to claim
         if !ask_confirm(
             format!(
-                "\nYou are about to transfer {}.\n\nAre you sure you want to continue? [Y/n]",
-                format!(
-                    "{} ORE",
-                    amount_to_ui_amount(amount, ore_api::consts::TOKEN_DECIMALS)
-                )
-                .bold(),
+                "\nYou are about to transfer {}.{}\n\nAre you sure you want to continue? [Y/n]",
+                format!("{} ORE", amount_u64_to_string(amount)).bold(),
+                memo_line(self.memo.as_deref()),
             )
             .as_str(),
         ) {
-            return;
+            return Ok(());
         }
 
         // Send and confirm
+        let mut ixs = transfer_ixs(pubkey, fee_payer, to, amount, create_recipient);
+        self.push_memo(&mut ixs, &fee_payer)?;
+        if let Some(path) = self.offline.as_ref() {
+            return self
+                .write_offline_transaction(&ixs, CU_LIMIT_CLAIM, fee_payer, path)
+                .await;
+        }
+        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false, None)
+            .await?;
+        Ok(())
+    }
+}
+
+// MI: the fee payer funds a new recipient token account, the signer only authorizes the transfer
+pub(crate) fn transfer_ixs(
+    signer: Pubkey,
+    fee_payer: Pubkey,
+    to: Pubkey,
+    amount: u64,
+    create_recipient: bool,
+) -> Vec<Instruction> {
+    let sender_tokens =
+        spl_associated_token_account::get_associated_token_address(&signer, &MINT_ADDRESS);
+    let recipient_tokens =
+        spl_associated_token_account::get_associated_token_address(&to, &MINT_ADDRESS);
+    let mut ixs = vec![];
+    if create_recipient {
         ixs.push(
-            spl_token::instruction::transfer(
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &fee_payer,
+                &to,
+                &MINT_ADDRESS,
                 &spl_token::id(),
-                &sender_tokens,
-                &recipient_tokens,
-                &pubkey,
-                &[&pubkey],
-                amount,
-            )
-            .unwrap(),
+            ),
         );
-        self.send_and_confirm(&ixs, ComputeBudget::Fixed(CU_LIMIT_CLAIM), false, None)
-            .await
-            .ok();
     }
+    ixs.push(
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &sender_tokens,
+            &recipient_tokens,
+            &signer,
+            &[&signer],
+            amount,
+        )
+        .unwrap(),
+    );
+    ixs
 }
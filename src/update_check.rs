@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use colored::*;
+use serde_json::Value;
+
+const CRATE_URL: &str = "https://crates.io/api/v1/crates/more-cli";
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// MI: look up the latest release in the background and print a notice when it is newer.
+// Nothing waits for it, and any failure is silent.
+pub fn spawn_update_check() {
+    tokio::spawn(async {
+        let Some(latest) = latest_version().await else {
+            return;
+        };
+        let current = env!("CARGO_PKG_VERSION");
+        let (Some(latest_parts), Some(current_parts)) =
+            (parse_version(&latest), parse_version(current))
+        else {
+            return;
+        };
+        if latest_parts.le(&current_parts) {
+            return;
+        }
+        let breaking = if latest_parts.0.gt(&current_parts.0) {
+            " It is a major release and may be required to keep mining with the on-chain program."
+        } else {
+            ""
+        };
+        eprintln!(
+            "{} ore-cli {} is available, you have {}. Update with `cargo install more-cli`.{}",
+            "NOTICE".bold(),
+            latest,
+            current,
+            breaking
+        );
+    });
+}
+
+async fn latest_version() -> Option<String> {
+    // crates.io rejects requests without a user agent
+    let response = reqwest::Client::new()
+        .get(CRATE_URL)
+        .header(
+            "User-Agent",
+            concat!("more-cli/", env!("CARGO_PKG_VERSION")),
+        )
+        .timeout(UPDATE_CHECK_TIMEOUT)
+        .send()
+        .await
+        .ok()?;
+    let body: Value = response.json().await.ok()?;
+    body["crate"]["max_stable_version"]
+        .as_str()
+        .map(str::to_string)
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
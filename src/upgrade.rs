@@ -3,17 +3,18 @@ use solana_sdk::{pubkey::Pubkey, signer::Signer};
 use spl_token::amount_to_ui_amount;
 
 use crate::{
+    args::UpgradeArgs,
     cu_limits::CU_LIMIT_UPGRADE,
     send_and_confirm::ComputeBudget,
     utils::{amount_f64_to_u64_v1, ask_confirm},
-    Miner, UpgradeArgs,
+    Error, Miner,
 };
 
 impl Miner {
-    pub async fn upgrade(&self, args: UpgradeArgs) {
-        let signer = &self.signer();
-        let beneficiary = self.get_or_initialize_ata().await;
-        let (sender, sender_balance) = self.get_ata_v1().await;
+    pub async fn upgrade(&self, args: UpgradeArgs) -> Result<(), Error> {
+        let signer = &self.signer()?;
+        let beneficiary = self.get_or_initialize_ata().await?;
+        let (sender, sender_balance) = self.get_ata_v1().await?;
 
         let amount_f64 = match args.amount {
             Some(f64) => f64,
@@ -35,7 +36,7 @@ impl Miner {
             )
             .as_str(),
         ) {
-            return;
+            return Ok(());
         }
 
         let ix = ore_api::instruction::upgrade(signer.pubkey(), beneficiary, sender, amount);
@@ -48,12 +49,13 @@ impl Miner {
                 println!("error: {}", err);
             }
         }
+        Ok(())
     }
 
     // asserts that token account exists and gets balance
-    async fn get_ata_v1(&self) -> (Pubkey, f64) {
+    async fn get_ata_v1(&self) -> Result<(Pubkey, f64), Error> {
         // Initialize client.
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
 
         // Derive assoicated token address (for v1 account)
@@ -64,30 +66,31 @@ impl Miner {
 
         // Get token account balance
         let balance = match client.get_token_account(&token_account_pubkey_v1).await {
-            Ok(None) => {
-                panic!("v1 token account doesn't exist")
-            }
+            Ok(None) => return Err(Error::Failed("v1 token account doesn't exist".to_string())),
             Ok(Some(token_account)) => match token_account.token_amount.ui_amount {
                 Some(ui_amount) => ui_amount,
                 None => {
-                    panic!(
+                    return Err(Error::Failed(format!(
                         "Error parsing token account UI amount: {}",
                         token_account.token_amount.amount
-                    )
+                    )))
                 }
             },
             Err(err) => {
-                panic!("Error fetching token account: {}", err)
+                return Err(Error::Failed(format!(
+                    "Error fetching token account: {}",
+                    err
+                )))
             }
         };
 
         // Return v1 token account address
-        (token_account_pubkey_v1, balance)
+        Ok((token_account_pubkey_v1, balance))
     }
 
-    async fn get_or_initialize_ata(&self) -> Pubkey {
+    async fn get_or_initialize_ata(&self) -> Result<Pubkey, Error> {
         // Initialize client
-        let signer = self.signer();
+        let signer = self.signer()?;
         let client = self.rpc_client.clone();
 
         // Derive assoicated token address (ata)
@@ -111,6 +114,6 @@ impl Miner {
         }
 
         // Return token account address
-        token_account_pubkey
+        Ok(token_account_pubkey)
     }
 }
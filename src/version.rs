@@ -0,0 +1,33 @@
+use serde_json::json;
+
+use crate::{args::VersionArgs, Error, Miner, OutputFormat};
+
+// MI: everything needed to triage a bug report
+impl Miner {
+    pub async fn version(&self, _args: VersionArgs) -> Result<(), Error> {
+        let versions = [
+            ("ore-cli", env!("CARGO_PKG_VERSION")),
+            ("git_commit", env!("ORE_CLI_GIT_COMMIT")),
+            ("drillx", env!("ORE_CLI_DRILLX_VERSION")),
+            ("ore-api", env!("ORE_CLI_ORE_API_VERSION")),
+            ("solana-sdk", env!("ORE_CLI_SOLANA_SDK_VERSION")),
+            ("rustc", env!("ORE_CLI_RUSTC_VERSION")),
+            ("target", env!("ORE_CLI_TARGET")),
+        ];
+        match self.output {
+            OutputFormat::Json => {
+                let map: serde_json::Map<String, serde_json::Value> = versions
+                    .iter()
+                    .map(|(name, version)| (name.to_string(), json!(version)))
+                    .collect();
+                println!("{}", serde_json::Value::Object(map));
+            }
+            OutputFormat::Text => {
+                for (name, version) in versions {
+                    println!("{}: {}", name, version);
+                }
+            }
+        }
+        Ok(())
+    }
+}
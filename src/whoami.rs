@@ -0,0 +1,48 @@
+use colored::*;
+use serde_json::json;
+use solana_sdk::signature::Signer;
+
+use crate::{
+    utils::{proof_pubkey, redact_url},
+    Error, Miner, OutputFormat,
+};
+
+impl Miner {
+    pub async fn whoami(&self) -> Result<(), Error> {
+        // Only public keys are resolved, private keys are never printed
+        let signer = self.signer()?.pubkey();
+        let fee_payer = self.fee_payer()?.pubkey();
+        let authority = self.authority()?;
+        let proof = proof_pubkey(authority);
+        let token_account = spl_associated_token_account::get_associated_token_address(
+            &authority,
+            &ore_api::consts::MINT_ADDRESS,
+        );
+        let rpc_url = redact_url(&self.rpc_client.url());
+
+        match self.output {
+            OutputFormat::Json => {
+                println!(
+                    "{}",
+                    json!({
+                        "signer": signer.to_string(),
+                        "fee_payer": fee_payer.to_string(),
+                        "authority": authority.to_string(),
+                        "proof": proof.to_string(),
+                        "token_account": token_account.to_string(),
+                        "rpc_url": rpc_url,
+                    })
+                );
+            }
+            OutputFormat::Text => {
+                println!("{}: {}", "Signer".bold(), signer);
+                println!("{}: {}", "Fee payer".bold(), fee_payer);
+                println!("{}: {}", "Authority".bold(), authority);
+                println!("{}: {}", "Proof".bold(), proof);
+                println!("{}: {}", "ORE token account".bold(), token_account);
+                println!("{}: {}", "RPC".bold(), rpc_url);
+            }
+        }
+        Ok(())
+    }
+}